@@ -12,7 +12,12 @@ pub mod print {
     Display an image.
     Example: {NAME} some-image.png
 
+    Directories are expanded into the images they contain. If a single image file is given, the
+    other images in its directory can be browsed as well.
+
     Options:
+      -r, --recursive   include images in subdirectories of directory arguments
+      -s, --skip-broken drop images that fail to load instead of showing an error
       -h, --help        display this help text and exit
       -v, --version     display version information and exit"
         );
@@ -68,7 +73,13 @@ pub mod args {
         /// User passed the "version" option.
         Version,
         /// Everything is good, the user passed some paths.
-        View { files: Vec<std::path::PathBuf> },
+        View {
+            files: Vec<std::path::PathBuf>,
+            /// Whether directories should be searched recursively.
+            recursive: bool,
+            /// Whether images that fail to load should be dropped from the list.
+            skip_broken: bool,
+        },
     }
 
     // --- Functions --- ///////////////////////////////////
@@ -79,9 +90,17 @@ pub mod args {
     /// element is not the executable name.
     pub fn parse(args: impl Iterator<Item = String>) -> Result<Request, Error> {
         let mut files = vec![];
+        let mut recursive = false;
+        let mut skip_broken = false;
 
         for arg in args {
             match arg.as_str() {
+                "-r" | "--recursive" => {
+                    recursive = true;
+                }
+                "-s" | "--skip-broken" => {
+                    skip_broken = true;
+                }
                 "-h" | "--help" => {
                     return Ok(Request::Help);
                 }
@@ -97,7 +116,11 @@ pub mod args {
         if files.is_empty() {
             Err(Error::MissingArgument)
         } else {
-            Ok(Request::View { files })
+            Ok(Request::View {
+                files,
+                recursive,
+                skip_broken,
+            })
         }
     }
 }
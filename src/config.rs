@@ -1,18 +1,34 @@
 //! Configuration file data types.
 
+use serde::de::{DeserializeOwned, IntoDeserializer};
 use serde::Deserialize;
 
 mod action;
+mod cache;
 mod color;
 mod grid;
 mod keymap;
+mod keyname;
 mod keys;
+mod mouse;
+mod osd;
+mod pixel_grid;
+mod scaling;
+mod status_bar;
+mod zoom;
 
-pub use action::Action;
+pub use action::{parse_scale, quarter_turns, Action, BoundAction};
+pub use cache::Cache;
 pub use color::Color;
 pub use grid::Grid;
 pub use keymap::KeyMap;
-pub use keys::KeyBind;
+pub use keys::{Key, KeyBind, Modifiers};
+pub use mouse::{MouseAction, MouseBind, MouseInput, MouseMap};
+pub use osd::{Osd, OsdPosition};
+pub use pixel_grid::PixelGrid;
+pub use scaling::{ScaleFilter, Scaling};
+pub use status_bar::{StatusBar, StatusBarPosition};
+pub use zoom::{Zoom, ZoomMode};
 
 /// Top-level structure of a configuration file.
 ///
@@ -26,17 +42,44 @@ pub use keys::KeyBind;
 pub struct Config {
     /// Distance in pixels the shift actions will move the image. (default 16)
     pub shift_amount: u16,
+    /// Drop images that fail to load from the list, instead of showing an error placeholder.
+    /// Can also be turned on with the `--skip-broken` option. (default false)
+    pub skip_broken: bool,
+    /// Rotate and mirror images to match their orientation metadata, such as the EXIF
+    /// Orientation tag used by digital cameras. (default true)
+    pub exif_orientation: bool,
     /// Background transparency grid settings.
     pub grid: Grid,
+    /// Pixel grid overlay settings.
+    pub pixel_grid: PixelGrid,
+    /// On-screen display settings.
+    pub osd: Osd,
+    /// Status bar settings.
+    pub status_bar: StatusBar,
+    /// Zoom settings.
+    pub zoom: Zoom,
+    /// Image scaling filter settings.
+    pub scaling: Scaling,
+    /// Image cache settings.
+    pub cache: Cache,
+    /// Drop the default keybinds, so only the binds in the config file are used. (default false)
+    pub clear_default_binds: bool,
+    /// Let digit keys build a count, which the next keybind repeats its action by. For example,
+    /// `5` then `Right` shifts the image five times. Turn this off to bind the digit keys
+    /// directly. (default true)
+    pub count_prefix: bool,
     /// Keybind definitions.
     pub binds: KeyMap,
+    /// Mouse button and wheel bind definitions.
+    pub mouse: MouseMap,
 }
 
 impl Config {
     /// Attempts to load the config file.
     ///
     /// Fir will always set its default settings and keybinds first. Values set in the config file
-    /// can override these defaults or--in the case of keybinds--add to them.
+    /// can override these defaults or--in the case of keybinds--add to them. Default keybinds can
+    /// be removed individually, or all at once with `clear_default_binds`.
     pub fn from_config_toml() -> Config {
         xdg::BaseDirectories::with_prefix(crate::meta::NAME).map_or_else(
             |err| {
@@ -51,7 +94,15 @@ impl Config {
                         None
                     },
                     |s| {
-                        toml::from_str(&s)
+                        toml::from_str::<Config>(&s)
+                            .map_err(|err| err.to_string())
+                            .and_then(|mut config| {
+                                config.check()?;
+                                if config.clear_default_binds {
+                                    config.binds.clear_defaults();
+                                }
+                                Ok(config)
+                            })
                             .map_err(|err| log::error!(target: "config", "{err}"))
                             .ok()
                     },
@@ -65,8 +116,215 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             shift_amount: 16,
+            skip_broken: false,
+            exif_orientation: true,
             grid: Grid::default(),
+            pixel_grid: PixelGrid::default(),
+            osd: Osd::default(),
+            status_bar: StatusBar::default(),
+            zoom: Zoom::default(),
+            scaling: Scaling::default(),
+            cache: Cache::default(),
+            clear_default_binds: false,
+            count_prefix: true,
             binds: KeyMap::default(),
+            mouse: MouseMap::default(),
         }
     }
 }
+
+// --- Runtime Options --- /////////////////////////////////
+
+/// Implements [`Config::set`] for a list of options, given as their names in the config file and
+/// the fields they're stored in.
+macro_rules! runtime_options {
+    ($($name:literal => $($field:ident).+,)*) => {
+        impl Config {
+            /// Names of the options which can be changed while the viewer is running.
+            pub const RUNTIME_OPTIONS: &'static [&'static str] = &[$($name),*];
+
+            /// Change an option while the viewer is running, such as `grid.size`.
+            ///
+            /// The value is written the same way as in the config file, except that strings may
+            /// be left unquoted. Options which only take effect at startup, like the cache sizes,
+            /// can't be changed. Values which the viewer can't work with are rejected, leaving the
+            /// option as it was.
+            ///
+            /// Most options take effect right away, but `scaling.high_quality_downscale` only
+            /// applies to images loaded after it's changed.
+            pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+                let mut changed = self.clone();
+                match name {
+                    $($name => changed.$($field).+ = parse_option(value)?,)*
+                    _ => return Err(format!("unknown option \"{name}\"")),
+                }
+                changed.check()?;
+                *self = changed;
+                Ok(())
+            }
+        }
+    };
+}
+
+runtime_options! {
+    "shift_amount" => shift_amount,
+    "grid.size" => grid.size,
+    "grid.color_dark" => grid.color_dark,
+    "grid.color_light" => grid.color_light,
+    "pixel_grid.enabled" => pixel_grid.enabled,
+    "pixel_grid.threshold" => pixel_grid.threshold,
+    "pixel_grid.color" => pixel_grid.color,
+    "pixel_grid.opacity" => pixel_grid.opacity,
+    "osd.enabled" => osd.enabled,
+    "osd.position" => osd.position,
+    "osd.template" => osd.template,
+    "osd.font_scale" => osd.font_scale,
+    "osd.color" => osd.color,
+    "osd.background" => osd.background,
+    "osd.background_opacity" => osd.background_opacity,
+    "osd.message_duration" => osd.message_duration,
+    "status_bar.enabled" => status_bar.enabled,
+    "status_bar.position" => status_bar.position,
+    "status_bar.template" => status_bar.template,
+    "status_bar.font_scale" => status_bar.font_scale,
+    "status_bar.color" => status_bar.color,
+    "status_bar.background" => status_bar.background,
+    "zoom.mode" => zoom.mode,
+    "zoom.factor" => zoom.factor,
+    "zoom.scroll_factor" => zoom.scroll_factor,
+    "zoom.presets" => zoom.presets,
+    "zoom.min" => zoom.min,
+    "zoom.max" => zoom.max,
+    "scaling.zoom_in" => scaling.zoom_in,
+    "scaling.zoom_out" => scaling.zoom_out,
+    "scaling.high_quality_downscale" => scaling.high_quality_downscale,
+    "scaling.downscale_threshold" => scaling.downscale_threshold,
+}
+
+/// Largest factor the built-in font can be scaled up by.
+///
+/// Text sizes are worked out in `u32`, which larger factors could overflow.
+const MAX_FONT_SCALE: u32 = 64;
+
+/// Longest time messages can be shown for, in seconds.
+const MAX_MESSAGE_DURATION: f32 = 24.0 * 60.0 * 60.0;
+
+impl Config {
+    /// Check that options have values the viewer can work with, so a typo in the config file or
+    /// the command line can't crash it.
+    fn check(&self) -> Result<(), String> {
+        let positive = |name: &str, value: f32| {
+            if value > 0.0 && value.is_finite() {
+                Ok(())
+            } else {
+                Err(format!("{name} must be greater than 0"))
+            }
+        };
+        if self.grid.size == 0 {
+            return Err("grid.size must be greater than 0".to_string());
+        }
+        positive("zoom.factor", self.zoom.factor)?;
+        positive("zoom.scroll_factor", self.zoom.scroll_factor)?;
+        positive("zoom.min", self.zoom.min)?;
+        positive("zoom.max", self.zoom.max)?;
+        if self.zoom.min > self.zoom.max {
+            return Err("zoom.min must not be greater than zoom.max".to_string());
+        }
+        for (name, font_scale) in [
+            ("osd.font_scale", self.osd.font_scale),
+            ("status_bar.font_scale", self.status_bar.font_scale),
+        ] {
+            if !(1..=MAX_FONT_SCALE).contains(&font_scale) {
+                return Err(format!("{name} must be from 1 to {MAX_FONT_SCALE}"));
+            }
+        }
+        if !(0.0..=MAX_MESSAGE_DURATION).contains(&self.osd.message_duration) {
+            return Err(format!(
+                "osd.message_duration must be from 0 to {MAX_MESSAGE_DURATION} seconds"
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Parse the value of an option given to [`Config::set`].
+///
+/// The value is parsed as TOML first. If that fails, it's taken as an unquoted string, so names
+/// like `Bottom` and colors like `#ff0000` don't need quotes.
+fn parse_option<T: DeserializeOwned>(value: &str) -> Result<T, String> {
+    #[derive(Deserialize)]
+    struct Value<T> {
+        value: T,
+    }
+    toml::from_str::<Value<T>>(&format!("value = {value}"))
+        .map(|parsed| parsed.value)
+        .or_else(|err| {
+            T::deserialize(value.into_deserializer())
+                .map_err(|_: serde::de::value::Error| err.message().to_string())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sets_options() {
+        let mut config = Config::default();
+        config.set("grid.size", "8").unwrap();
+        assert_eq!(config.grid.size, 8);
+        config.set("osd.enabled", "false").unwrap();
+        assert!(!config.osd.enabled);
+        config.set("zoom.factor", "1.5").unwrap();
+        assert_eq!(config.zoom.factor, 1.5);
+        config
+            .set("osd.template", "\"{index} of {total}\"")
+            .unwrap();
+        assert_eq!(config.osd.template, "{index} of {total}");
+    }
+
+    #[test]
+    fn accepts_unquoted_strings() {
+        let mut config = Config::default();
+        config.set("status_bar.position", "Top").unwrap();
+        assert_eq!(config.status_bar.position, StatusBarPosition::Top);
+        config.set("grid.color_dark", "#ff0000").unwrap();
+        assert_eq!(config.grid.color_dark.as_rgb(), (0xff, 0x00, 0x00));
+        config.set("osd.template", "{index}").unwrap();
+        assert_eq!(config.osd.template, "{index}");
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        let mut config = Config::default();
+        for (name, value) in [
+            ("grid.size", "0"),
+            ("grid.size", "eight"),
+            ("grid.color_dark", "#ff00"),
+            ("status_bar.position", "Middle"),
+            ("zoom.factor", "0"),
+            ("zoom.scroll_factor", "-1.1"),
+            ("zoom.factor", "inf"),
+            ("zoom.min", "200"),
+            ("osd.font_scale", "0"),
+            ("status_bar.font_scale", "999999999"),
+            ("osd.message_duration", "1e19"),
+            ("osd.message_duration", "nan"),
+            ("osd.message_duration", "-1"),
+            ("cache.decoded_size", "64"),
+            ("nonsense", "1"),
+        ] {
+            assert!(
+                config.set(name, value).is_err(),
+                "{name} = {value} was accepted"
+            );
+        }
+        // Rejected values leave the options as they were.
+        let default = Config::default();
+        assert_eq!(config.grid.size, default.grid.size);
+        assert_eq!(config.zoom.factor, default.zoom.factor);
+        assert_eq!(config.zoom.min, default.zoom.min);
+        assert_eq!(config.osd.font_scale, default.osd.font_scale);
+        assert_eq!(config.osd.message_duration, default.osd.message_duration);
+    }
+}
@@ -1,8 +1,10 @@
 use enum_iterator::Sequence;
+use serde::de::value::MapAccessDeserializer;
+use serde::de::{IntoDeserializer, Visitor};
 use serde::Deserialize;
 
 /// This enum lists all of the basic operations supported by the viewer.
-#[derive(Clone, Copy, Debug, Deserialize, Sequence)]
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Sequence)]
 pub enum Action {
     /// Shift the image up by a fixed amount.
     ShiftUp,
@@ -12,14 +14,406 @@ pub enum Action {
     ShiftLeft,
     /// Shift the image right by a fixed amount.
     ShiftRight,
-    /// Clear the image transformations to reset it to the center of the screen.
+    /// Clear the image transformations to reset it to the center of the screen, with its original
+    /// orientation and the default zoom mode.
     ResetTransform,
     /// Zoom in by a fixed factor.
     ZoomIn,
     /// Zoom out by a fixed factor.
     ZoomOut,
+    /// Display the image at 1:1 scale.
+    ActualSize,
+    /// Scale the image to fit inside the window.
+    FitWindow,
+    /// Scale the image to fit the width of the window.
+    FitWidth,
+    /// Scale the image to fit the height of the window.
+    FitHeight,
+    /// Scale the image to cover the whole window.
+    Fill,
+    /// Rotate the image a quarter turn clockwise.
+    RotateClockwise,
+    /// Rotate the image a quarter turn counter-clockwise.
+    RotateCounterClockwise,
+    /// Mirror the image horizontally.
+    FlipHorizontal,
+    /// Mirror the image vertically.
+    FlipVertical,
+    /// Switch between the configured scaling filters and nearest-neighbor filtering.
+    ToggleSmoothing,
+    /// Show or hide the pixel grid overlay.
+    TogglePixelGrid,
+    /// Show or hide the on-screen display.
+    ToggleOsd,
+    /// Show or hide a list of every action and the keys bound to it.
+    ShowHelp,
+    /// Open the command line at the bottom of the window.
+    CommandPrompt,
+    /// Pause or resume playback of an animated image.
+    TogglePlayback,
+    /// Pause an animated image and step to its next frame.
+    NextFrame,
+    /// Pause an animated image and step to its previous frame.
+    PrevFrame,
     /// Toggle the viewer window's fullscreen status.
     ToggleFullscreen,
+    /// Display the next image in the list.
+    NextImage,
+    /// Display the previous image in the list.
+    PrevImage,
+    /// Display the first image in the list.
+    FirstImage,
+    /// Display the last image in the list.
+    LastImage,
     /// Exit the viewer.
     Quit,
 }
+
+// --- BoundAction --- /////////////////////////////////////
+
+/// What a bind does: either a basic [`Action`], or an operation which takes parameters.
+///
+/// Parameterized actions let different keys do coarse and fine versions of the same operation. In
+/// the config file, they can be written as a string, with the parameters after the name:
+/// - `"Shift 0 -64"` moves the image by a distance in pixels.
+/// - `"Zoom 1.1"` scales the image by a factor.
+/// - `"ZoomTo 200%"` sets the scale, either as a percentage or a factor like `2.0`.
+/// - `"Rotate 90"` rotates the image by a multiple of 90 degrees clockwise.
+///
+/// They can also be written as a table, like `{ action = "Shift", dx = 0, dy = -64 }`. The
+/// parameters are named `dx` and `dy` for `Shift`, `factor` for `Zoom`, `scale` for `ZoomTo`, and
+/// `degrees` for `Rotate`. Parameters which the action doesn't take are an error. Zoom factors and
+/// scales must be positive.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BoundAction {
+    /// A basic action.
+    Action(Action),
+    /// Move the image by a distance in pixels.
+    Shift { dx: i32, dy: i32 },
+    /// Scale the image by a factor.
+    Zoom(f32),
+    /// Set the scale of the image, where 1.0 is 1:1 scale.
+    ZoomTo(f32),
+    /// Rotate the image by a number of quarter turns clockwise.
+    Rotate(i32),
+}
+
+impl From<Action> for BoundAction {
+    fn from(action: Action) -> Self {
+        Self::Action(action)
+    }
+}
+
+impl std::fmt::Display for BoundAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Action(action) => write!(f, "{action:?}"),
+            Self::Shift { dx, dy } => write!(f, "Shift {dx} {dy}"),
+            Self::Zoom(factor) => write!(f, "Zoom {factor}"),
+            Self::ZoomTo(scale) => write!(f, "ZoomTo {}%", scale * 100.0),
+            Self::Rotate(quarter_turns) => write!(f, "Rotate {}", quarter_turns * 90),
+        }
+    }
+}
+
+impl std::str::FromStr for BoundAction {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split([' ', ',']).filter(|word| !word.is_empty());
+        let name = words.next().unwrap_or_default();
+        let args = words.collect::<Vec<_>>();
+        let arg = |i: usize| {
+            args.get(i)
+                .copied()
+                .ok_or_else(|| format!("\"{name}\" is missing a parameter"))
+        };
+        let action = match name {
+            "Shift" => Self::Shift {
+                dx: parse_number(arg(0)?)?,
+                dy: parse_number(arg(1)?)?,
+            },
+            "Zoom" => Self::Zoom(check_positive(parse_number(arg(0)?)?, "zoom factor")?),
+            "ZoomTo" => Self::ZoomTo(parse_scale(arg(0)?)?),
+            "Rotate" => Self::Rotate(quarter_turns(parse_number(arg(0)?)?)?),
+            _ => {
+                let action = Action::deserialize(name.into_deserializer())
+                    .map_err(|err: serde::de::value::Error| err.to_string())?;
+                return match args.as_slice() {
+                    [] => Ok(Self::Action(action)),
+                    _ => Err(format!("\"{name}\" doesn't take any parameters")),
+                };
+            }
+        };
+        match args.len() {
+            n if n > action.parameter_count() => Err(format!("\"{name}\" has too many parameters")),
+            _ => Ok(action),
+        }
+    }
+}
+
+impl BoundAction {
+    /// Names of the actions which take parameters.
+    const PARAMETERIZED: [&'static str; 4] = ["Shift", "Zoom", "ZoomTo", "Rotate"];
+
+    /// Names of every action, basic or parameterized, as they're written in the config file.
+    pub fn names() -> impl Iterator<Item = String> {
+        enum_iterator::all::<Action>()
+            .map(|action| format!("{action:?}"))
+            .chain(Self::PARAMETERIZED.map(String::from))
+    }
+
+    /// Number of parameters the action takes in its string form.
+    fn parameter_count(&self) -> usize {
+        match self {
+            Self::Action(_) => 0,
+            Self::Shift { .. } => 2,
+            Self::Zoom(_) | Self::ZoomTo(_) | Self::Rotate(_) => 1,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BoundAction {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(BoundActionVisitor)
+    }
+}
+
+/// The table form of a [`BoundAction`].
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BoundActionTable {
+    action: String,
+    dx: Option<i32>,
+    dy: Option<i32>,
+    factor: Option<f32>,
+    scale: Option<ScaleValue>,
+    degrees: Option<i32>,
+}
+
+/// A scale in a table, which may be a factor or a percentage string.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScaleValue {
+    Factor(f32),
+    Text(String),
+}
+
+struct BoundActionVisitor;
+impl<'de> Visitor<'de> for BoundActionVisitor {
+    type Value = BoundAction;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("an action name, optionally followed by parameters, or a table")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        v.parse().map_err(E::custom)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        use serde::de::Error;
+        let table = BoundActionTable::deserialize(MapAccessDeserializer::new(map))?;
+        let missing = |field| A::Error::custom(format!("\"{}\" needs {field}", table.action));
+
+        let parameters: &[&str] = match table.action.as_str() {
+            "Shift" => &["dx", "dy"],
+            "Zoom" => &["factor"],
+            "ZoomTo" => &["scale"],
+            "Rotate" => &["degrees"],
+            _ => &[],
+        };
+        let given = [
+            ("dx", table.dx.is_some()),
+            ("dy", table.dy.is_some()),
+            ("factor", table.factor.is_some()),
+            ("scale", table.scale.is_some()),
+            ("degrees", table.degrees.is_some()),
+        ];
+        if let Some((field, _)) = given
+            .iter()
+            .find(|(field, given)| *given && !parameters.contains(field))
+        {
+            return Err(A::Error::custom(format!(
+                "\"{}\" doesn't take a \"{field}\" parameter",
+                table.action
+            )));
+        }
+
+        match table.action.as_str() {
+            "Shift" => Ok(BoundAction::Shift {
+                dx: table.dx.unwrap_or(0),
+                dy: table.dy.unwrap_or(0),
+            }),
+            "Zoom" => {
+                let factor = table.factor.ok_or_else(|| missing("a factor"))?;
+                check_positive(factor, "zoom factor")
+                    .map(BoundAction::Zoom)
+                    .map_err(A::Error::custom)
+            }
+            "ZoomTo" => match table.scale.ok_or_else(|| missing("a scale"))? {
+                ScaleValue::Factor(scale) => check_positive(scale, "scale")
+                    .map(BoundAction::ZoomTo)
+                    .map_err(A::Error::custom),
+                ScaleValue::Text(text) => parse_scale(&text)
+                    .map(BoundAction::ZoomTo)
+                    .map_err(A::Error::custom),
+            },
+            "Rotate" => {
+                let degrees = table.degrees.ok_or_else(|| missing("degrees"))?;
+                quarter_turns(degrees)
+                    .map(BoundAction::Rotate)
+                    .map_err(A::Error::custom)
+            }
+            name => name.parse().map_err(A::Error::custom),
+        }
+    }
+}
+
+/// Parse a number parameter.
+fn parse_number<T: std::str::FromStr>(s: &str) -> Result<T, String> {
+    s.parse()
+        .map_err(|_| format!("\"{s}\" is not a valid number"))
+}
+
+/// Parse a scale, given either as a factor like `2.0` or a percentage like `200%`.
+pub fn parse_scale(s: &str) -> Result<f32, String> {
+    let scale = match s.strip_suffix('%') {
+        Some(percent) => parse_number::<f32>(percent)? / 100.0,
+        None => parse_number(s)?,
+    };
+    check_positive(scale, "scale").map_err(|_| format!("\"{s}\" is not a valid scale"))
+}
+
+/// Check that a zoom factor or scale is positive and finite.
+fn check_positive(value: f32, what: &str) -> Result<f32, String> {
+    if value > 0.0 && value.is_finite() {
+        Ok(value)
+    } else {
+        Err(format!("{value} is not a valid {what}"))
+    }
+}
+
+/// Convert an angle in degrees clockwise to quarter turns.
+pub fn quarter_turns(degrees: i32) -> Result<i32, String> {
+    if degrees % 90 == 0 {
+        Ok(degrees / 90)
+    } else {
+        Err(format!(
+            "can only rotate by multiples of 90 degrees, not {degrees}"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_basic_actions() {
+        assert_eq!(
+            "NextImage".parse(),
+            Ok(BoundAction::Action(Action::NextImage))
+        );
+        assert!("NextImage 2".parse::<BoundAction>().is_err());
+        assert!("Nonsense".parse::<BoundAction>().is_err());
+    }
+
+    #[test]
+    fn checks_parameter_counts() {
+        assert_eq!(
+            "Shift 0 -64".parse(),
+            Ok(BoundAction::Shift { dx: 0, dy: -64 })
+        );
+        assert_eq!(
+            "Shift 8, 8".parse(),
+            Ok(BoundAction::Shift { dx: 8, dy: 8 })
+        );
+        assert!("Shift 8".parse::<BoundAction>().is_err());
+        assert!("Shift 1 2 3".parse::<BoundAction>().is_err());
+        assert!("Zoom".parse::<BoundAction>().is_err());
+        assert!("Zoom 1.1 1.2".parse::<BoundAction>().is_err());
+    }
+
+    #[test]
+    fn parses_zoom_factors() {
+        assert_eq!("Zoom 1.5".parse(), Ok(BoundAction::Zoom(1.5)));
+        assert!("Zoom 0".parse::<BoundAction>().is_err());
+        assert!("Zoom -2".parse::<BoundAction>().is_err());
+        assert!("Zoom inf".parse::<BoundAction>().is_err());
+        assert!("Zoom NaN".parse::<BoundAction>().is_err());
+    }
+
+    #[test]
+    fn parses_percent_scales() {
+        assert_eq!("ZoomTo 200%".parse(), Ok(BoundAction::ZoomTo(2.0)));
+        assert_eq!("ZoomTo 0.5".parse(), Ok(BoundAction::ZoomTo(0.5)));
+        assert!("ZoomTo 0%".parse::<BoundAction>().is_err());
+    }
+
+    #[test]
+    fn rotates_by_quarter_turns() {
+        assert_eq!("Rotate 90".parse(), Ok(BoundAction::Rotate(1)));
+        assert_eq!("Rotate -180".parse(), Ok(BoundAction::Rotate(-2)));
+        assert_eq!("Rotate 0".parse(), Ok(BoundAction::Rotate(0)));
+        assert!("Rotate 45".parse::<BoundAction>().is_err());
+        assert!("Rotate 1.5".parse::<BoundAction>().is_err());
+    }
+
+    #[test]
+    fn parses_scales() {
+        assert_eq!(parse_scale("150%"), Ok(1.5));
+        assert_eq!(parse_scale("2"), Ok(2.0));
+        assert_eq!(parse_scale("0.25"), Ok(0.25));
+        assert!(parse_scale("0").is_err());
+        assert!(parse_scale("-50%").is_err());
+        assert!(parse_scale("%").is_err());
+        assert!(parse_scale("big").is_err());
+        assert!(parse_scale("inf").is_err());
+    }
+
+    /// Parse the table form of an action.
+    fn parse_table(table: &str) -> Result<BoundAction, toml::de::Error> {
+        #[derive(Deserialize)]
+        struct Bind {
+            bind: BoundAction,
+        }
+        toml::from_str::<Bind>(&format!("bind = {table}")).map(|bind| bind.bind)
+    }
+
+    #[test]
+    fn parses_tables() {
+        assert_eq!(
+            parse_table(r#"{ action = "Shift", dy = -64 }"#).ok(),
+            Some(BoundAction::Shift { dx: 0, dy: -64 })
+        );
+        assert_eq!(
+            parse_table(r#"{ action = "ZoomTo", scale = "50%" }"#).ok(),
+            Some(BoundAction::ZoomTo(0.5))
+        );
+        assert!(parse_table(r#"{ action = "ZoomTo", scale = 0 }"#).is_err());
+        assert!(parse_table(r#"{ action = "Zoom", factor = -1.0 }"#).is_err());
+        assert!(parse_table(r#"{ action = "Zoom", scale = 2 }"#).is_err());
+        assert!(parse_table(r#"{ action = "NextImage", dx = 1 }"#).is_err());
+    }
+
+    #[test]
+    fn round_trips_through_display() {
+        for action in [
+            BoundAction::Shift { dx: -8, dy: 16 },
+            BoundAction::Zoom(1.25),
+            BoundAction::ZoomTo(2.0),
+            BoundAction::Rotate(3),
+        ] {
+            assert_eq!(action.to_string().parse(), Ok(action));
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use serde::Deserialize;
+
+/// Configuration options for caching loaded images.
+///
+/// Images are cached twice over: once as decoded image data in main memory, and once as textures,
+/// which usually live in video memory.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Cache {
+    /// Memory budget for decoded images, in MiB. Animations are cut short if their frames would
+    /// take up more than this. (default 512)
+    pub decoded_size: usize,
+    /// Memory budget for textures, in MiB. (default 512)
+    pub texture_size: usize,
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Self {
+            decoded_size: 512,
+            texture_size: 512,
+        }
+    }
+}
@@ -50,6 +50,10 @@ impl<'de> Visitor<'de> for ColorVisitor {
         E: serde::de::Error,
     {
         const ERRMSG: &str = "colors must be in RGB hex code format \"#rrggbb\"";
+        // Check the length first, so slicing can't panic on short or non-ASCII strings.
+        if v.len() != 7 || !v.starts_with('#') || !v.is_ascii() {
+            return Err(E::custom(ERRMSG));
+        }
         let r = u8::from_str_radix(&v[1..3], 16).map_err(|_| E::custom(ERRMSG))?;
         let g = u8::from_str_radix(&v[3..5], 16).map_err(|_| E::custom(ERRMSG))?;
         let b = u8::from_str_radix(&v[5..7], 16).map_err(|_| E::custom(ERRMSG))?;
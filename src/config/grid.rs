@@ -1,4 +1,4 @@
-use super::color::Color;
+use super::Color;
 use serde::Deserialize;
 
 /// Configuration options for the background transparency grid.
@@ -1,13 +1,21 @@
 use sdl2::keyboard::Keycode;
+use serde::de::value::MapAccessDeserializer;
 use serde::de::Visitor;
 use serde::Deserialize;
 
-use super::Action;
-use super::KeyBind;
+use super::{Action, BoundAction};
+use super::{Key, KeyBind, Modifiers};
 use std::collections::HashMap;
 
 /// Maps keys to actions.
 ///
+/// Keys may be combined with modifiers, so `Right` and `Shift+Right` are separate binds. See
+/// [`KeyBind`]. Keys may also be bound to actions with parameters, such as `"ZoomTo 200%"`. See
+/// [`BoundAction`].
+///
+/// Binds from the config file are layered over the default binds. A key can be unbound by binding
+/// it to `"None"`, and the defaults can be dropped altogether with [`Self::clear_defaults`].
+///
 /// The default keybinds are:
 /// - Up Arrow => Shift Up
 /// - Down Arrow => Shift Down
@@ -16,35 +24,118 @@ use std::collections::HashMap;
 /// - Keypad 0 => Reset Transform
 /// - Keypad Plus => Zoom In
 /// - Keypad Minus => Zoom Out
+/// - = => Actual Size
+/// - W => Fit Window
+/// - R => Rotate Clockwise
+/// - L => Rotate Counter-Clockwise
+/// - H => Flip Horizontal
+/// - V => Flip Vertical
+/// - S => Toggle Smoothing
+/// - G => Toggle Pixel Grid
+/// - I => Toggle OSD
+/// - F1 => Show Help
+/// - : => Command Prompt
+/// - Space => Toggle Playback
+/// - . => Next Frame
+/// - , => Previous Frame
 /// - F => Toggle Fullscreen
+/// - Page Down => Next Image
+/// - Page Up => Previous Image
+/// - Home => First Image
+/// - End => Last Image
 /// - Q => Quit
 #[derive(Clone, Debug)]
 pub struct KeyMap {
-    /// Binds a key to an action.
-    binds: HashMap<KeyBind, Action>,
+    /// Binds set in the config file. `None` means the key was unbound.
+    binds: HashMap<KeyBind, Option<BoundAction>>,
+    /// Default binds, which apply to keys the config file doesn't mention.
+    defaults: HashMap<KeyBind, BoundAction>,
 }
 
 impl KeyMap {
-    /// Get the [`Action`] bound to a given key.
-    pub fn get(&self, bind: &KeyBind) -> Option<&Action> {
-        self.binds.get(bind)
+    /// Get the [`BoundAction`] bound to a given key.
+    pub fn get(&self, bind: &KeyBind) -> Option<&BoundAction> {
+        match self.binds.get(bind) {
+            Some(action) => action.as_ref(),
+            None => self.defaults.get(bind),
+        }
+    }
+
+    /// Get every key bound to a given [`BoundAction`], sorted by name.
+    pub fn keys_for(&self, action: BoundAction) -> Vec<KeyBind> {
+        let mut keys = self
+            .iter()
+            .filter(|(_, bound)| **bound == action)
+            .map(|(key, _)| *key)
+            .collect::<Vec<_>>();
+        keys.sort_by_cached_key(KeyBind::to_string);
+        keys
+    }
+
+    /// Iterate over every key which is bound to an action.
+    pub fn iter(&self) -> impl Iterator<Item = (&KeyBind, &BoundAction)> {
+        let configured = self
+            .binds
+            .iter()
+            .filter_map(|(key, action)| Some((key, action.as_ref()?)));
+        let defaults = self
+            .defaults
+            .iter()
+            .filter(|(key, _)| !self.binds.contains_key(key));
+        configured.chain(defaults)
+    }
+
+    /// Drop the default binds, leaving only the binds set in the config file.
+    pub fn clear_defaults(&mut self) {
+        self.defaults.clear();
     }
 }
 
 impl Default for KeyMap {
     fn default() -> Self {
+        // On layouts like US QWERTY, ":" is typed as Shift+; and reported as such.
+        let shift = Modifiers {
+            shift: true,
+            ..Modifiers::default()
+        };
         Self {
-            binds: HashMap::from([
-                (KeyBind(Keycode::Up), Action::ShiftUp),
-                (KeyBind(Keycode::Down), Action::ShiftDown),
-                (KeyBind(Keycode::Left), Action::ShiftLeft),
-                (KeyBind(Keycode::Right), Action::ShiftRight),
-                (KeyBind(Keycode::Kp0), Action::ResetTransform),
-                (KeyBind(Keycode::KpPlus), Action::ZoomIn),
-                (KeyBind(Keycode::KpMinus), Action::ZoomOut),
-                (KeyBind(Keycode::F), Action::ToggleFullscreen),
-                (KeyBind(Keycode::Q), Action::Quit),
-            ]),
+            binds: HashMap::new(),
+            defaults: [
+                (KeyBind::from(Keycode::Up), Action::ShiftUp),
+                (KeyBind::from(Keycode::Down), Action::ShiftDown),
+                (KeyBind::from(Keycode::Left), Action::ShiftLeft),
+                (KeyBind::from(Keycode::Right), Action::ShiftRight),
+                (KeyBind::from(Keycode::Kp0), Action::ResetTransform),
+                (KeyBind::from(Keycode::KpPlus), Action::ZoomIn),
+                (KeyBind::from(Keycode::KpMinus), Action::ZoomOut),
+                (KeyBind::from(Keycode::Equals), Action::ActualSize),
+                (KeyBind::from(Keycode::W), Action::FitWindow),
+                (KeyBind::from(Keycode::R), Action::RotateClockwise),
+                (KeyBind::from(Keycode::L), Action::RotateCounterClockwise),
+                (KeyBind::from(Keycode::H), Action::FlipHorizontal),
+                (KeyBind::from(Keycode::V), Action::FlipVertical),
+                (KeyBind::from(Keycode::S), Action::ToggleSmoothing),
+                (KeyBind::from(Keycode::G), Action::TogglePixelGrid),
+                (KeyBind::from(Keycode::I), Action::ToggleOsd),
+                (KeyBind::from(Keycode::F1), Action::ShowHelp),
+                (KeyBind::from(Keycode::Colon), Action::CommandPrompt),
+                (
+                    KeyBind::new(Key::Code(Keycode::Semicolon), shift),
+                    Action::CommandPrompt,
+                ),
+                (KeyBind::from(Keycode::Space), Action::TogglePlayback),
+                (KeyBind::from(Keycode::Period), Action::NextFrame),
+                (KeyBind::from(Keycode::Comma), Action::PrevFrame),
+                (KeyBind::from(Keycode::F), Action::ToggleFullscreen),
+                (KeyBind::from(Keycode::PageDown), Action::NextImage),
+                (KeyBind::from(Keycode::PageUp), Action::PrevImage),
+                (KeyBind::from(Keycode::Home), Action::FirstImage),
+                (KeyBind::from(Keycode::End), Action::LastImage),
+                (KeyBind::from(Keycode::Q), Action::Quit),
+            ]
+            .into_iter()
+            .map(|(key, action)| (key, BoundAction::from(action)))
+            .collect(),
         }
     }
 }
@@ -72,8 +163,20 @@ impl<'de> Visitor<'de> for KeyMapVisitor {
     {
         let mut km = Self::Value::default();
         loop {
-            match map.next_entry::<KeyBind, Action>() {
-                Ok(Some((k, v))) => km.binds.insert(k, v),
+            match map.next_entry::<KeyBind, Binding>() {
+                Ok(Some((k, Binding(v)))) => {
+                    // Different names can refer to the same key (eg. "PageDown" and "Next").
+                    // Scancodes aren't compared with keycodes, since which keycode a scancode
+                    // produces depends on the keyboard layout.
+                    if let Some(old) = km.binds.insert(k, v) {
+                        log::warn!(
+                            target: "config",
+                            "\"{k}\" is bound more than once; {} replaces {}",
+                            describe(v),
+                            describe(old),
+                        );
+                    }
+                }
                 Ok(None) => break,
                 Err(e) => return Err(e),
             };
@@ -81,3 +184,52 @@ impl<'de> Visitor<'de> for KeyMapVisitor {
         Ok(km)
     }
 }
+
+/// Describe the target of a bind for log messages.
+fn describe(action: Option<BoundAction>) -> String {
+    action.map_or("None".to_string(), |action| action.to_string())
+}
+
+// --- Binding --- /////////////////////////////////////////
+
+/// The value of an entry in the binds table: either an action, or `"None"` to unbind the key.
+struct Binding(Option<BoundAction>);
+
+impl<'de> Deserialize<'de> for Binding {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(BindingVisitor)
+    }
+}
+
+struct BindingVisitor;
+impl<'de> Visitor<'de> for BindingVisitor {
+    type Value = Binding;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("an action, or \"None\" to unbind the key")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        match v {
+            "None" => Ok(Binding(None)),
+            _ => v
+                .parse()
+                .map(|action| Binding(Some(action)))
+                .map_err(E::custom),
+        }
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        BoundAction::deserialize(MapAccessDeserializer::new(map))
+            .map(|action| Binding(Some(action)))
+    }
+}
@@ -0,0 +1,227 @@
+//! Parsing of key names in the config file.
+//!
+//! Keys can be named in three ways:
+//! - SDL key names, like `"Keypad +"` or `PageDown`. Since many of these aren't valid TOML bare
+//!   keys, they may need to be quoted. Underscores may also be used in place of spaces.
+//! - XKB keysym names, like `KP_Add` or `Page_Down`, as used by most Linux tools.
+//! - Scancodes, like `scancode:87`, which name a physical key regardless of the keyboard layout.
+
+use sdl2::keyboard::{Keycode, Scancode};
+
+use super::keys::Key;
+
+/// Prefix of a key name that gives a scancode.
+pub const SCANCODE_PREFIX: &str = "scancode:";
+
+/// Maximum number of suggestions offered for a misspelled key name.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Parse a key name, without any modifiers.
+///
+/// If the name isn't recognized, the error lists the closest known names.
+pub fn parse_key(name: &str) -> Result<Key, String> {
+    if let Some(code) = strip_prefix_ignore_case(name, SCANCODE_PREFIX) {
+        return code
+            .trim()
+            .parse()
+            .ok()
+            .and_then(Scancode::from_i32)
+            .map(Key::Scan)
+            .ok_or_else(|| format!("\"{code}\" is not a valid scancode"));
+    }
+    let xkb = XKB_KEYSYMS
+        .iter()
+        .find(|(keysym, _)| keysym.eq_ignore_ascii_case(name))
+        .map(|(_, sdl_name)| *sdl_name);
+    // XKB names come first, since SDL uses some of the same names for other keys (eg. "Prior").
+    // Underscores used to be the only way to write SDL names with spaces in them, so they're
+    // still accepted in place of spaces.
+    [xkb, Some(name), Some(&name.replace('_', " "))]
+        .into_iter()
+        .flatten()
+        .find_map(Keycode::from_name)
+        .map(Key::Code)
+        .ok_or_else(|| unknown_key_error(name))
+}
+
+/// Describe a key name which couldn't be parsed, listing the known names closest to it.
+fn unknown_key_error(name: &str) -> String {
+    let lowercase = name.to_lowercase();
+    let sdl_names = (0..Scancode::Num as i32)
+        .filter_map(Scancode::from_i32)
+        .map(Scancode::name)
+        .filter(|sdl_name| !sdl_name.is_empty());
+    let xkb_names = XKB_KEYSYMS.iter().map(|(keysym, _)| *keysym);
+
+    // Allow roughly one typo for every three characters.
+    let threshold = (lowercase.chars().count() / 3).max(1);
+    let mut matches = sdl_names
+        .chain(xkb_names)
+        .map(|candidate| {
+            (
+                edit_distance(&lowercase, &candidate.to_lowercase()),
+                candidate,
+            )
+        })
+        .filter(|(distance, _)| *distance <= threshold)
+        .collect::<Vec<_>>();
+    matches.sort();
+    matches.dedup_by_key(|(_, candidate)| candidate.to_lowercase());
+
+    let suggestions = matches
+        .iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| format!("\"{candidate}\""))
+        .collect::<Vec<_>>();
+    match suggestions.as_slice() {
+        [] => format!("unknown key \"{name}\""),
+        [one] => format!("unknown key \"{name}\"; did you mean {one}?"),
+        many => format!(
+            "unknown key \"{name}\"; did you mean one of {}?",
+            many.join(", ")
+        ),
+    }
+}
+
+/// Strip `prefix` from the start of `s`, ignoring ASCII case.
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = s.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix)
+        .then(|| &s[prefix.len()..])
+}
+
+/// Number of single-character insertions, deletions and substitutions needed to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// XKB keysym names, and the SDL key names they correspond to.
+///
+/// Keys which are named the same way by both, like letters, digits, function keys and the arrow
+/// keys, aren't listed.
+const XKB_KEYSYMS: &[(&str, &str)] = &[
+    ("BackSpace", "Backspace"),
+    ("space", "Space"),
+    ("Prior", "PageUp"),
+    ("Page_Up", "PageUp"),
+    ("Next", "PageDown"),
+    ("Page_Down", "PageDown"),
+    ("Print", "PrintScreen"),
+    ("Caps_Lock", "CapsLock"),
+    ("Num_Lock", "Numlock"),
+    ("Scroll_Lock", "ScrollLock"),
+    ("Shift_L", "Left Shift"),
+    ("Shift_R", "Right Shift"),
+    ("Control_L", "Left Ctrl"),
+    ("Control_R", "Right Ctrl"),
+    ("Alt_L", "Left Alt"),
+    ("Alt_R", "Right Alt"),
+    ("Super_L", "Left GUI"),
+    ("Super_R", "Right GUI"),
+    ("KP_0", "Keypad 0"),
+    ("KP_1", "Keypad 1"),
+    ("KP_2", "Keypad 2"),
+    ("KP_3", "Keypad 3"),
+    ("KP_4", "Keypad 4"),
+    ("KP_5", "Keypad 5"),
+    ("KP_6", "Keypad 6"),
+    ("KP_7", "Keypad 7"),
+    ("KP_8", "Keypad 8"),
+    ("KP_9", "Keypad 9"),
+    ("KP_Add", "Keypad +"),
+    ("KP_Subtract", "Keypad -"),
+    ("KP_Multiply", "Keypad *"),
+    ("KP_Divide", "Keypad /"),
+    ("KP_Decimal", "Keypad ."),
+    ("KP_Equal", "Keypad ="),
+    ("KP_Enter", "Keypad Enter"),
+    ("plus", "+"),
+    ("minus", "-"),
+    ("equal", "="),
+    ("comma", ","),
+    ("period", "."),
+    ("slash", "/"),
+    ("backslash", "\\"),
+    ("semicolon", ";"),
+    ("colon", ":"),
+    ("apostrophe", "'"),
+    ("quotedbl", "\""),
+    ("grave", "`"),
+    ("bracketleft", "["),
+    ("bracketright", "]"),
+    ("less", "<"),
+    ("greater", ">"),
+    ("question", "?"),
+    ("exclam", "!"),
+    ("at", "@"),
+    ("numbersign", "#"),
+    ("dollar", "$"),
+    ("percent", "%"),
+    ("ampersand", "&"),
+    ("asterisk", "*"),
+    ("parenleft", "("),
+    ("parenright", ")"),
+    ("underscore", "_"),
+    ("asciicircum", "^"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sdl_names() {
+        assert_eq!(parse_key("Q"), Ok(Key::Code(Keycode::Q)));
+        assert_eq!(parse_key("PageDown"), Ok(Key::Code(Keycode::PageDown)));
+        assert_eq!(parse_key("Keypad +"), Ok(Key::Code(Keycode::KpPlus)));
+        assert_eq!(parse_key("Keypad_+"), Ok(Key::Code(Keycode::KpPlus)));
+    }
+
+    #[test]
+    fn parses_xkb_names() {
+        assert_eq!(parse_key("KP_Add"), Ok(Key::Code(Keycode::KpPlus)));
+        assert_eq!(parse_key("Page_Down"), Ok(Key::Code(Keycode::PageDown)));
+        // SDL calls a different key "Prior", so the XKB name has to win.
+        assert_eq!(parse_key("Prior"), Ok(Key::Code(Keycode::PageUp)));
+    }
+
+    #[test]
+    fn parses_scancodes() {
+        assert_eq!(parse_key("scancode:20"), Ok(Key::Scan(Scancode::Q)));
+        assert_eq!(parse_key("Scancode: 20"), Ok(Key::Scan(Scancode::Q)));
+        assert!(parse_key("scancode:").is_err());
+        assert!(parse_key("scancode:Q").is_err());
+        assert!(parse_key("scancode:-1").is_err());
+    }
+
+    #[test]
+    fn suggests_close_names() {
+        let err = parse_key("PageDwn").unwrap_err();
+        assert!(err.contains("\"PageDown\""), "{err}");
+        assert_eq!(
+            parse_key("Nonsense"),
+            Err("unknown key \"Nonsense\"".to_string())
+        );
+    }
+
+    #[test]
+    fn counts_edits() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("pagedown", "pagedown"), 0);
+        assert_eq!(edit_distance("pagedwn", "pagedown"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+}
@@ -1,19 +1,46 @@
-use derive_more::From;
-use sdl2::keyboard::Keycode;
+use sdl2::keyboard::{Keycode, Mod, Scancode};
 use serde::{de::Visitor, Deserialize};
 
+use super::keyname::{self, SCANCODE_PREFIX};
+
 /// A struct for naming keys.
 ///
-/// Presently, this is just a NewType wrapper around an [`sdl2::keyboard::Keycode`].
-#[derive(Clone, Copy, Debug, From, PartialEq, Eq, Hash)]
-pub struct KeyBind(pub Keycode);
+/// A keybind is a key, along with the modifier keys which must be held down when it's pressed. In
+/// the config file, modifiers are written before the key name and joined with `+`, like
+/// `"Ctrl+Shift+Left"`. Key names may be SDL key names, XKB keysym names, or scancodes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyBind {
+    /// The key which triggers the bind.
+    pub key: Key,
+    /// Modifiers which must be held down. Modifiers which aren't listed must not be.
+    pub modifiers: Modifiers,
+}
+
+impl KeyBind {
+    /// Construct a new keybind from a key and a set of modifiers.
+    pub fn new(key: Key, modifiers: Modifiers) -> Self {
+        Self { key, modifiers }
+    }
+}
+
+impl From<Keycode> for KeyBind {
+    /// A keybind for a key pressed without any modifiers.
+    fn from(key: Keycode) -> Self {
+        Self::new(Key::Code(key), Modifiers::default())
+    }
+}
+
+impl std::fmt::Display for KeyBind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.modifiers, self.key)
+    }
+}
 
 impl std::str::FromStr for KeyBind {
-    type Err = &'static str;
+    type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Keycode::from_name(s)
-            .map(KeyBind::from)
-            .ok_or("not a valid key name")
+        let (modifiers, key) = Modifiers::split_prefix(s);
+        keyname::parse_key(key).map(|key| KeyBind::new(key, modifiers))
     }
 }
 
@@ -31,16 +58,164 @@ impl<'de> Visitor<'de> for KeyBindVisitor {
     type Value = KeyBind;
 
     fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-        formatter.write_str("a key name")
+        formatter.write_str("a key name, optionally preceded by modifiers (eg. \"Ctrl+Q\")")
     }
 
     fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
     where
         E: serde::de::Error,
     {
-        // TODO: Not all SDL key names are valid toml keys (eg. "Keypad +"). It would be better to
-        // use XKB keysym names in the future.
-        log::info!("v: '{v}' = '{}'", v.replace("_", " "));
-        v.replace("_", " ").parse().map_err(E::custom)
+        v.parse().map_err(E::custom)
+    }
+}
+
+// --- Key --- /////////////////////////////////////////////
+
+/// A key on the keyboard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// A key identified by the symbol it produces in the current keyboard layout.
+    Code(Keycode),
+    /// A key identified by its physical position on the keyboard.
+    Scan(Scancode),
+}
+
+impl std::fmt::Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Code(keycode) => f.write_str(&keycode.name()),
+            Self::Scan(scancode) => write!(f, "{SCANCODE_PREFIX}{}", *scancode as i32),
+        }
+    }
+}
+
+// --- Modifiers --- ///////////////////////////////////////
+
+/// The modifier keys held down for a [`KeyBind`].
+///
+/// The left and right variants of each modifier are treated as the same key.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    /// Either Control key.
+    pub ctrl: bool,
+    /// Either Shift key.
+    pub shift: bool,
+    /// Either Alt key.
+    pub alt: bool,
+    /// Either Super key, also known as the Windows or Command key.
+    pub super_key: bool,
+}
+
+impl Modifiers {
+    /// Split the modifiers off the front of a bind name like `"Ctrl+Shift+Left"`.
+    ///
+    /// Returns the modifiers and the rest of the name. Names may contain `+` themselves (eg.
+    /// "Keypad +"), so only prefixes which are modifier names are split off.
+    pub fn split_prefix(name: &str) -> (Self, &str) {
+        let mut modifiers = Self::default();
+        let mut rest = name;
+        while let Some((prefix, tail)) = rest.split_once('+') {
+            if tail.is_empty() || !modifiers.set(prefix) {
+                break;
+            }
+            rest = tail;
+        }
+        (modifiers, rest)
+    }
+
+    /// Mark the modifier called `name` as held. Names are case-insensitive.
+    ///
+    /// Returns `false` if `name` isn't a modifier.
+    fn set(&mut self, name: &str) -> bool {
+        let held = match name.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => &mut self.ctrl,
+            "shift" => &mut self.shift,
+            "alt" => &mut self.alt,
+            "super" | "gui" | "meta" => &mut self.super_key,
+            _ => return false,
+        };
+        *held = true;
+        true
+    }
+}
+
+impl std::fmt::Display for Modifiers {
+    /// Writes each held modifier followed by a `+`, so the name of a key can be appended.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (held, name) in [
+            (self.ctrl, "Ctrl"),
+            (self.shift, "Shift"),
+            (self.alt, "Alt"),
+            (self.super_key, "Super"),
+        ] {
+            if held {
+                write!(f, "{name}+")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<Mod> for Modifiers {
+    /// The modifiers held according to SDL. Lock keys such as Caps Lock and Num Lock are ignored.
+    fn from(keymod: Mod) -> Self {
+        Self {
+            ctrl: keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD),
+            shift: keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD),
+            alt: keymod.intersects(Mod::LALTMOD | Mod::RALTMOD),
+            super_key: keymod.intersects(Mod::LGUIMOD | Mod::RGUIMOD),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bind(key: Keycode, ctrl: bool, shift: bool) -> KeyBind {
+        let modifiers = Modifiers {
+            ctrl,
+            shift,
+            ..Modifiers::default()
+        };
+        KeyBind::new(Key::Code(key), modifiers)
+    }
+
+    #[test]
+    fn parses_modifier_prefixes() {
+        assert_eq!("Q".parse(), Ok(bind(Keycode::Q, false, false)));
+        assert_eq!("Ctrl+Q".parse(), Ok(bind(Keycode::Q, true, false)));
+        assert_eq!("control+shift+q".parse(), Ok(bind(Keycode::Q, true, true)));
+        assert_eq!("Shift+Ctrl+Q".parse(), Ok(bind(Keycode::Q, true, true)));
+    }
+
+    #[test]
+    fn keeps_plus_in_key_names() {
+        assert_eq!("Keypad +".parse(), Ok(bind(Keycode::KpPlus, false, false)));
+        assert_eq!(
+            "Ctrl+Keypad +".parse(),
+            Ok(bind(Keycode::KpPlus, true, false))
+        );
+        assert_eq!(
+            "Ctrl+KP_Add".parse(),
+            Ok(bind(Keycode::KpPlus, true, false))
+        );
+    }
+
+    #[test]
+    fn parses_modified_scancodes() {
+        let modifiers = Modifiers {
+            alt: true,
+            ..Modifiers::default()
+        };
+        assert_eq!(
+            "Alt+scancode:20".parse(),
+            Ok(KeyBind::new(Key::Scan(Scancode::Q), modifiers))
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_modifiers() {
+        assert!("Hyper+Q".parse::<KeyBind>().is_err());
     }
 }
@@ -0,0 +1,332 @@
+use sdl2::mouse::MouseButton;
+use serde::de::value::MapAccessDeserializer;
+use serde::de::{Error, Visitor};
+use serde::Deserialize;
+
+use super::Modifiers;
+use super::{Action, BoundAction};
+use std::collections::HashMap;
+
+/// Maps mouse buttons and the mouse wheel to actions.
+///
+/// Like keys, mouse inputs may be combined with modifiers, such as `Ctrl+WheelUp`. Binds from the
+/// config file are layered over the default binds, and a default can be removed by binding its
+/// input to `"None"`.
+///
+/// The default mouse binds are:
+/// - Left => Pan
+/// - Double Left => Toggle Fullscreen
+/// - Back (X1) => Previous Image
+/// - Forward (X2) => Next Image
+/// - Wheel Up => Zoom
+/// - Wheel Down => Zoom
+#[derive(Clone, Debug)]
+pub struct MouseMap {
+    /// Binds set in the config file. `None` means the input was unbound.
+    binds: HashMap<MouseBind, Option<MouseAction>>,
+    /// Default binds, which apply to inputs the config file doesn't mention.
+    defaults: HashMap<MouseBind, MouseAction>,
+}
+
+impl MouseMap {
+    /// Get the [`MouseAction`] bound to a given input.
+    pub fn get(&self, bind: &MouseBind) -> Option<&MouseAction> {
+        match self.binds.get(bind) {
+            Some(action) => action.as_ref(),
+            None => self.defaults.get(bind),
+        }
+    }
+}
+
+impl Default for MouseMap {
+    fn default() -> Self {
+        let bind = |input| MouseBind::new(input, Modifiers::default());
+        Self {
+            binds: HashMap::new(),
+            defaults: HashMap::from([
+                (
+                    bind(MouseInput::Button(MouseButton::Left)),
+                    MouseAction::Pan,
+                ),
+                (
+                    bind(MouseInput::DoubleClick(MouseButton::Left)),
+                    MouseAction::Action(Action::ToggleFullscreen.into()),
+                ),
+                (
+                    bind(MouseInput::Button(MouseButton::X1)),
+                    MouseAction::Action(Action::PrevImage.into()),
+                ),
+                (
+                    bind(MouseInput::Button(MouseButton::X2)),
+                    MouseAction::Action(Action::NextImage.into()),
+                ),
+                (bind(MouseInput::WheelUp), MouseAction::Zoom),
+                (bind(MouseInput::WheelDown), MouseAction::Zoom),
+            ]),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for MouseMap {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_map(MouseMapVisitor)
+    }
+}
+
+struct MouseMapVisitor;
+impl<'de> Visitor<'de> for MouseMapVisitor {
+    type Value = MouseMap;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a mapping of mouse inputs to actions")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        let mut mm = Self::Value::default();
+        while let Some((bind, MouseBinding(action))) =
+            map.next_entry::<MouseBind, MouseBinding>()?
+        {
+            match (action, bind.input.is_wheel()) {
+                (Some(MouseAction::Zoom), false) => {
+                    return Err(A::Error::custom(format!(
+                        "\"{bind}\" can't be bound to Zoom, which only works with the wheel; \
+                         use ZoomDrag to zoom by dragging"
+                    )));
+                }
+                (Some(MouseAction::Pan | MouseAction::ZoomDrag), true) => {
+                    return Err(A::Error::custom(format!(
+                        "\"{bind}\" can't be bound to Pan or ZoomDrag, which need a button to \
+                         drag with"
+                    )));
+                }
+                _ => (),
+            }
+            if mm.binds.insert(bind, action).is_some() {
+                log::warn!(target: "config", "\"{bind}\" is bound more than once");
+            }
+        }
+        Ok(mm)
+    }
+}
+
+// --- MouseBind --- ///////////////////////////////////////
+
+/// A mouse input, along with the modifier keys which must be held down.
+///
+/// In the config file, these are written like key binds, eg. `"Ctrl+Right"` or `DoubleLeft`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MouseBind {
+    /// The mouse input which triggers the bind.
+    pub input: MouseInput,
+    /// Modifiers which must be held down. Modifiers which aren't listed must not be.
+    pub modifiers: Modifiers,
+}
+
+impl MouseBind {
+    /// Construct a new mouse bind from an input and a set of modifiers.
+    pub fn new(input: MouseInput, modifiers: Modifiers) -> Self {
+        Self { input, modifiers }
+    }
+}
+
+impl std::fmt::Display for MouseBind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.modifiers, self.input)
+    }
+}
+
+impl std::str::FromStr for MouseBind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (modifiers, input) = Modifiers::split_prefix(s);
+        input.parse().map(|input| MouseBind::new(input, modifiers))
+    }
+}
+
+impl<'de> Deserialize<'de> for MouseBind {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_string(MouseBindVisitor)
+    }
+}
+
+struct MouseBindVisitor;
+impl<'de> Visitor<'de> for MouseBindVisitor {
+    type Value = MouseBind;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a mouse input, optionally preceded by modifiers (eg. \"Ctrl+Left\")")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        v.parse().map_err(E::custom)
+    }
+}
+
+// --- MouseInput --- //////////////////////////////////////
+
+/// Something the user can do with the mouse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MouseInput {
+    /// Pressing a button. The button may be held down to drag.
+    Button(MouseButton),
+    /// Pressing a button twice in quick succession.
+    DoubleClick(MouseButton),
+    /// Scrolling the wheel up.
+    WheelUp,
+    /// Scrolling the wheel down.
+    WheelDown,
+    /// Scrolling the wheel left, or tilting it on mice which support that.
+    WheelLeft,
+    /// Scrolling the wheel right, or tilting it on mice which support that.
+    WheelRight,
+}
+
+impl MouseInput {
+    /// Whether the input is a movement of the wheel, rather than a button press.
+    pub fn is_wheel(&self) -> bool {
+        matches!(
+            self,
+            Self::WheelUp | Self::WheelDown | Self::WheelLeft | Self::WheelRight
+        )
+    }
+}
+
+/// Names of the mouse buttons in the config file.
+const BUTTON_NAMES: [(&str, MouseButton); 7] = [
+    ("Left", MouseButton::Left),
+    ("Middle", MouseButton::Middle),
+    ("Right", MouseButton::Right),
+    ("X1", MouseButton::X1),
+    ("X2", MouseButton::X2),
+    ("Back", MouseButton::X1),
+    ("Forward", MouseButton::X2),
+];
+
+/// Prefix of a button name which makes it a double-click.
+const DOUBLE_CLICK_PREFIX: &str = "Double";
+
+impl std::fmt::Display for MouseInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let button_name = |button: &MouseButton| {
+            BUTTON_NAMES
+                .iter()
+                .find(|(_, b)| b == button)
+                .map_or("Unknown", |(name, _)| name)
+        };
+        match self {
+            Self::Button(button) => f.write_str(button_name(button)),
+            Self::DoubleClick(button) => write!(f, "{DOUBLE_CLICK_PREFIX}{}", button_name(button)),
+            Self::WheelUp => f.write_str("WheelUp"),
+            Self::WheelDown => f.write_str("WheelDown"),
+            Self::WheelLeft => f.write_str("WheelLeft"),
+            Self::WheelRight => f.write_str("WheelRight"),
+        }
+    }
+}
+
+impl std::str::FromStr for MouseInput {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let button = |name: &str| {
+            BUTTON_NAMES
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|(_, button)| *button)
+        };
+        let wheel = [
+            Self::WheelUp,
+            Self::WheelDown,
+            Self::WheelLeft,
+            Self::WheelRight,
+        ]
+        .into_iter()
+        .find(|wheel| wheel.to_string().eq_ignore_ascii_case(s));
+
+        if let Some(input) = button(s).map(Self::Button).or(wheel) {
+            return Ok(input);
+        }
+        s.get(..DOUBLE_CLICK_PREFIX.len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(DOUBLE_CLICK_PREFIX))
+            .and_then(|_| button(&s[DOUBLE_CLICK_PREFIX.len()..]))
+            .map(Self::DoubleClick)
+            .ok_or_else(|| {
+                format!(
+                    "unknown mouse input \"{s}\"; expected a button (Left, Middle, Right, X1/Back \
+                     or X2/Forward), a button prefixed with \"Double\", or WheelUp, WheelDown, \
+                     WheelLeft or WheelRight"
+                )
+            })
+    }
+}
+
+// --- MouseAction --- /////////////////////////////////////
+
+/// What a mouse input does.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MouseAction {
+    /// Move the image while the button is held down. Only applies to buttons.
+    Pan,
+    /// Zoom around the point where the button was pressed, as the mouse is dragged up and down.
+    /// Only applies to buttons.
+    ZoomDrag,
+    /// Zoom around the mouse cursor, in the direction the wheel is scrolled. Only applies to the
+    /// wheel.
+    Zoom,
+    /// Perform an action once. The action may take parameters, like a key bind.
+    Action(BoundAction),
+}
+
+/// The value of an entry in the mouse table: a mouse action, or `"None"` to unbind the input.
+struct MouseBinding(Option<MouseAction>);
+
+impl<'de> Deserialize<'de> for MouseBinding {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(MouseBindingVisitor)
+    }
+}
+
+struct MouseBindingVisitor;
+impl<'de> Visitor<'de> for MouseBindingVisitor {
+    type Value = MouseBinding;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("\"Pan\", \"ZoomDrag\", \"Zoom\", an action name, or \"None\"")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        let action = match v {
+            "None" => None,
+            "Pan" => Some(MouseAction::Pan),
+            "ZoomDrag" => Some(MouseAction::ZoomDrag),
+            "Zoom" => Some(MouseAction::Zoom),
+            _ => Some(MouseAction::Action(v.parse().map_err(E::custom)?)),
+        };
+        Ok(MouseBinding(action))
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        let action = BoundAction::deserialize(MapAccessDeserializer::new(map))?;
+        Ok(MouseBinding(Some(MouseAction::Action(action))))
+    }
+}
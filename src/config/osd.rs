@@ -0,0 +1,58 @@
+use super::Color;
+use serde::Deserialize;
+
+/// Configuration options for the on-screen display.
+///
+/// The OSD is a box of text drawn over a corner of the image, showing details about it. Transient
+/// messages, such as feedback from toggling a setting, are shown in the same box.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Osd {
+    /// Whether the OSD is shown at startup. (default true)
+    pub enabled: bool,
+    /// Corner of the window the OSD is drawn in. (default TopLeft)
+    pub position: OsdPosition,
+    /// Text of the OSD. The placeholders `{filename}`, `{path}`, `{index}`, `{total}`, `{width}`,
+    /// `{height}`, `{zoom}`, `{format}` and `{filesize}` are replaced with details of the current
+    /// image. (default
+    /// "{filename}\n{index}/{total}  {width}x{height}  {zoom}")
+    pub template: String,
+    /// Factor the 8x8 pixel font is scaled up by, from 1 to 64. (default 2)
+    pub font_scale: u32,
+    /// Color of the text. (default #ffffff)
+    pub color: Color,
+    /// Color of the box behind the text. (default #000000)
+    pub background: Color,
+    /// Opacity of the box behind the text, from 0.0 to 1.0. (default 0.6)
+    pub background_opacity: f32,
+    /// How long transient messages are shown for, in seconds, up to a day. (default 2.0)
+    pub message_duration: f32,
+}
+
+impl Default for Osd {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            position: OsdPosition::TopLeft,
+            template: "{filename}\n{index}/{total}  {width}x{height}  {zoom}".to_string(),
+            font_scale: 2,
+            color: Color::from_rgb(0xff, 0xff, 0xff),
+            background: Color::from_rgb(0x00, 0x00, 0x00),
+            background_opacity: 0.6,
+            message_duration: 2.0,
+        }
+    }
+}
+
+/// Corners of the window the OSD can be drawn in.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub enum OsdPosition {
+    /// The top-left corner of the window.
+    TopLeft,
+    /// The top-right corner of the window.
+    TopRight,
+    /// The bottom-left corner of the window.
+    BottomLeft,
+    /// The bottom-right corner of the window.
+    BottomRight,
+}
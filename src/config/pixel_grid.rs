@@ -0,0 +1,29 @@
+use super::Color;
+use serde::Deserialize;
+
+/// Configuration options for the pixel grid overlay.
+///
+/// When zoomed in far enough, lines are drawn along the boundaries between image pixels.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct PixelGrid {
+    /// Whether the pixel grid is shown at startup. (default true)
+    pub enabled: bool,
+    /// Scale at and above which the pixel grid is drawn. (default 8.0)
+    pub threshold: f32,
+    /// Color of the grid lines. (default #000000)
+    pub color: Color,
+    /// Opacity of the grid lines, from 0.0 to 1.0. (default 0.5)
+    pub opacity: f32,
+}
+
+impl Default for PixelGrid {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold: 8.0,
+            color: Color::from_rgb(0x00, 0x00, 0x00),
+            opacity: 0.5,
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use serde::Deserialize;
+
+/// Configuration options for how images are filtered when they are scaled.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Scaling {
+    /// Filter used when the image is displayed larger than 1:1 scale. (default Nearest)
+    pub zoom_in: ScaleFilter,
+    /// Filter used when the image is displayed smaller than 1:1 scale. (default Linear)
+    pub zoom_out: ScaleFilter,
+    /// Downscale images on the CPU with a Lanczos filter when they are displayed far below 1:1
+    /// scale. This looks better than the GPU filters, but keeps an extra copy of each image in
+    /// memory. (default false)
+    pub high_quality_downscale: bool,
+    /// Scale below which high-quality downscaling kicks in. (default 0.5)
+    pub downscale_threshold: f32,
+}
+
+impl Default for Scaling {
+    fn default() -> Self {
+        Self {
+            zoom_in: ScaleFilter::Nearest,
+            zoom_out: ScaleFilter::Linear,
+            high_quality_downscale: false,
+            downscale_threshold: 0.5,
+        }
+    }
+}
+
+/// Texture filtering methods.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub enum ScaleFilter {
+    /// Nearest-neighbor sampling. Keeps pixel art and screenshots crisp.
+    Nearest,
+    /// Linear filtering. Smooth, suits photos.
+    Linear,
+    /// Anisotropic filtering, if the renderer supports it.
+    Best,
+}
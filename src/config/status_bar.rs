@@ -0,0 +1,47 @@
+use super::Color;
+use serde::Deserialize;
+
+/// Configuration options for the status bar.
+///
+/// The status bar is a line of text along the top or bottom edge of the window. Unlike the OSD, it
+/// doesn't cover the image; the image is displayed in the rest of the window.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct StatusBar {
+    /// Whether the status bar is shown. (default false)
+    pub enabled: bool,
+    /// Edge of the window the status bar is drawn along. (default Bottom)
+    pub position: StatusBarPosition,
+    /// Text of the status bar. It takes the same placeholders as the OSD template. (default
+    /// "{index}/{total}  {filename}  {width}x{height}  {format}  {filesize}  {zoom}")
+    pub template: String,
+    /// Factor the 8x8 pixel font is scaled up by, from 1 to 64. (default 2)
+    pub font_scale: u32,
+    /// Color of the text. (default #ffffff)
+    pub color: Color,
+    /// Color of the bar behind the text. (default #202020)
+    pub background: Color,
+}
+
+impl Default for StatusBar {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            position: StatusBarPosition::Bottom,
+            template: "{index}/{total}  {filename}  {width}x{height}  {format}  {filesize}  {zoom}"
+                .to_string(),
+            font_scale: 2,
+            color: Color::from_rgb(0xff, 0xff, 0xff),
+            background: Color::from_rgb(0x20, 0x20, 0x20),
+        }
+    }
+}
+
+/// Edges of the window the status bar can be drawn along.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub enum StatusBarPosition {
+    /// The top edge of the window.
+    Top,
+    /// The bottom edge of the window.
+    Bottom,
+}
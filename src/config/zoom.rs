@@ -0,0 +1,91 @@
+use serde::Deserialize;
+
+/// Configuration options for zooming.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Zoom {
+    /// Zoom mode applied when an image is opened. (default FitWindow)
+    pub mode: ZoomMode,
+    /// Factor the zoom actions scale the image by. (default 1.5)
+    pub factor: f32,
+    /// Factor the image is scaled by for each notch of the mouse wheel. (default 1.25)
+    pub scroll_factor: f32,
+    /// Preset scales for the zoom actions to stop at, where 1.0 is 1:1 scale. If this is not
+    /// empty, it is used instead of [`Self::factor`]. (default empty)
+    pub presets: Vec<f32>,
+    /// Smallest allowed scale. (default 0.01)
+    pub min: f32,
+    /// Largest allowed scale. (default 100.0)
+    pub max: f32,
+}
+
+impl Default for Zoom {
+    fn default() -> Self {
+        Self {
+            mode: ZoomMode::FitWindow,
+            factor: 1.5,
+            scroll_factor: 1.25,
+            presets: vec![],
+            min: 0.01,
+            max: 100.0,
+        }
+    }
+}
+
+/// Rules for choosing the image scale based on the size of the window.
+///
+/// While a zoom mode is active, it is re-applied whenever the window is resized.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub enum ZoomMode {
+    /// Display the image at 1:1 scale.
+    ActualSize,
+    /// Scale the image so it fits entirely inside the window.
+    FitWindow,
+    /// Scale the image so its width matches the window's.
+    FitWidth,
+    /// Scale the image so its height matches the window's.
+    FitHeight,
+    /// Scale the image so it covers the entire window.
+    Fill,
+}
+
+impl Zoom {
+    /// The smallest preset scale which is larger than `scale`.
+    pub fn preset_above(&self, scale: f32) -> Option<f32> {
+        self.presets
+            .iter()
+            .copied()
+            .filter(|&p| p > scale * PRESET_TOLERANCE)
+            .min_by(f32::total_cmp)
+    }
+
+    /// The largest preset scale which is smaller than `scale`.
+    pub fn preset_below(&self, scale: f32) -> Option<f32> {
+        self.presets
+            .iter()
+            .copied()
+            .filter(|&p| p < scale / PRESET_TOLERANCE)
+            .max_by(f32::total_cmp)
+    }
+}
+
+/// Scales within this ratio of a preset are considered to be at that preset.
+///
+/// Without this, rounding errors could leave the scale just shy of a preset, so zooming would
+/// appear to do nothing.
+const PRESET_TOLERANCE: f32 = 1.001;
+
+impl ZoomMode {
+    /// Calculate the scale this mode gives an image of size `image` in a window of size `window`.
+    pub fn scale_for(&self, image: (u32, u32), window: (u32, u32)) -> f32 {
+        let sx = window.0 as f32 / image.0.max(1) as f32;
+        let sy = window.1 as f32 / image.1.max(1) as f32;
+        match self {
+            Self::ActualSize => 1.0,
+            Self::FitWindow => sx.min(sy),
+            Self::FitWidth => sx,
+            Self::FitHeight => sy,
+            Self::Fill => sx.max(sy),
+        }
+    }
+}
@@ -1,60 +1,91 @@
 //! Fir SDL2 frontend.
 
 use appview::AppView;
+use loader::Loader;
 use texture::TextureFactory;
 
 use crate::config::Config;
+use crate::imagelist::ImageList;
 use crate::meta;
 
-use image::ImageReader;
 use std::error::Error;
-use std::path::PathBuf;
+use std::time::Instant;
 
+mod animation;
 mod appview;
+mod cache;
+mod decode;
+mod downscale;
+mod loader;
+mod orientation;
+mod prompt;
+mod text;
 mod texture;
 
-const MIN_SIZE: (u32, u32) = (256, 256);
-
 /// Entry point of the GUI.
 ///
 /// This function serves as the core event loop of the program. It initializes SDL2, creates the
-/// window, loads and displays each image file, and manages the event loop.
+/// window, and hands the image list over to an [`AppView`], which displays the images and lets the
+/// user move back and forth between them. The loop exits once the view stops running.
+///
+/// `recursive` is the `--recursive` command-line option, which the view also applies to paths
+/// opened from its command line.
 ///
-/// TODO: At present, each image is displayed in a unique window, one-by-one. This means that the
-/// user has to use the quit action to display the next image, and can't go back to the previous
-/// one. In the future, it would be better to have actions for moving forward and backward through
-/// the image list, so the quit action actually just exits the whole program.
-pub fn start(files: Vec<PathBuf>, config: Config) -> Result<(), Box<dyn Error>> {
+/// The loop blocks while waiting for events, so an idle viewer uses no CPU. The view is only
+/// redrawn when its dirty flag is set, and only woken up without an event if it asks to be with
+/// [`AppView::next_wakeup`].
+pub fn start(images: ImageList, config: Config, recursive: bool) -> Result<(), Box<dyn Error>> {
     let context = sdl2::init()?;
     let video = context.video()?;
-    let window = video.window(meta::NAME, 0, 0).resizable().build()?;
+    let window = video
+        .window(meta::NAME, appview::MIN_SIZE.0, appview::MIN_SIZE.1)
+        .resizable()
+        .build()?;
+    // SDL starts out accepting text input, which may bring up an input method or on-screen
+    // keyboard. It's only needed while the command line is open.
+    video.text_input().stop();
 
     let mut canvas = window.into_canvas().build()?;
-    let img_factory = TextureFactory::new(canvas.texture_creator());
-
-    for path in files {
-        let image = ImageReader::open(&path)?.decode()?;
-        let window = canvas.window_mut();
-        let _ = window.set_size(
-            image.width().max(MIN_SIZE.0),
-            image.height().max(MIN_SIZE.1),
-        );
-        let _ = window.set_title(format!("{} - {}", meta::NAME, path.display()).as_str());
-
-        let image_texture = img_factory.construct_from_image(&image)?;
-        let mut view = AppView::new(&mut canvas, &config, image_texture);
+    let info = canvas.info();
+    let img_factory = TextureFactory::new(
+        canvas.texture_creator(),
+        (info.max_texture_width, info.max_texture_height),
+    );
+
+    let loader = Loader::new(&config, &context.event()?)?;
+    let mut view = AppView::new(
+        &mut canvas,
+        &config,
+        &img_factory,
+        loader,
+        images,
+        recursive,
+    )?;
+
+    let mut events = context.event_pump()?;
+    while view.is_running() {
         view.present();
 
-        let mut events = context.event_pump()?;
-        'mainloop: loop {
-            for event in events.poll_iter() {
-                view.handle_event(&event);
-                if !view.is_running() {
-                    break 'mainloop;
-                }
+        // Sleep until something happens, or until the view next needs to update itself.
+        let event = match view.next_wakeup() {
+            Some(deadline) => {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                // Round up, so the loop doesn't spin through the last millisecond before the
+                // deadline. SDL takes the timeout as a C int.
+                let millis = timeout.as_micros().div_ceil(1000).min(i32::MAX as u128);
+                events.wait_event_timeout(millis as u32)
+            }
+            None => Some(events.wait_event()),
+        };
+
+        // Handle every event that has piled up before redrawing.
+        for event in event.into_iter().chain(events.poll_iter()) {
+            view.handle_event(&event);
+            if !view.is_running() {
+                break;
             }
-            view.present();
         }
+        view.update(Instant::now());
     }
     Ok(())
 }
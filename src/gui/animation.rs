@@ -0,0 +1,87 @@
+//! Playback of animated images.
+
+use std::time::{Duration, Instant};
+
+use super::texture::TiledTexture;
+
+/// An animation, made up of a texture for each frame.
+///
+/// Animations loop forever. They don't keep time on their own; instead the owner asks when the
+/// next frame is due with [`Self::next_wakeup`], and advances the animation with [`Self::update`].
+pub struct Animation<'a> {
+    /// Each frame, along with how long it should be shown.
+    frames: Vec<(TiledTexture<'a>, Duration)>,
+    /// Index of the frame being shown.
+    current: usize,
+    /// When the next frame is due, or `None` if playback is paused.
+    next_frame_at: Option<Instant>,
+}
+
+impl<'a> Animation<'a> {
+    /// Construct a new animation from its frames, and start playing it.
+    ///
+    /// # Panics
+    /// Panics if `frames` is empty.
+    pub fn new(frames: Vec<(TiledTexture<'a>, Duration)>) -> Self {
+        assert!(!frames.is_empty(), "animation must have at least one frame");
+        let first_delay = frames[0].1;
+        Self {
+            frames,
+            current: 0,
+            next_frame_at: Some(Instant::now() + first_delay),
+        }
+    }
+
+    /// Texture of the frame being shown.
+    pub fn texture(&self) -> &TiledTexture<'a> {
+        &self.frames[self.current].0
+    }
+
+    /// Iterate over the textures of every frame.
+    pub fn textures(&self) -> impl Iterator<Item = &TiledTexture<'a>> {
+        self.frames.iter().map(|(texture, _)| texture)
+    }
+
+    /// When the next frame is due, or `None` if playback is paused.
+    pub fn next_wakeup(&self) -> Option<Instant> {
+        self.next_frame_at
+    }
+
+    /// Advance playback up to the time `now`.
+    ///
+    /// Returns `true` if the current frame changed.
+    pub fn update(&mut self, now: Instant) -> bool {
+        let Some(mut due) = self.next_frame_at else {
+            return false;
+        };
+        // If we've fallen far behind (eg. the system was suspended), don't bother catching up
+        // frame by frame.
+        let loop_length: Duration = self.frames.iter().map(|(_, delay)| *delay).sum();
+        if now.saturating_duration_since(due) > loop_length {
+            due = now;
+        }
+
+        let start = self.current;
+        while due <= now {
+            self.current = (self.current + 1) % self.frames.len();
+            due += self.frames[self.current].1;
+        }
+        self.next_frame_at = Some(due);
+        self.current != start
+    }
+
+    /// Pause playback if it's playing, or resume it if it's paused.
+    pub fn toggle_playback(&mut self) {
+        self.next_frame_at = match self.next_frame_at {
+            Some(_) => None,
+            None => Some(Instant::now() + self.frames[self.current].1),
+        };
+    }
+
+    /// Pause playback and step forward or backward by a number of frames, wrapping around.
+    pub fn step(&mut self, frames: i32) {
+        self.next_frame_at = None;
+        let len = self.frames.len() as i64;
+        self.current = (self.current as i64 + i64::from(frames)).rem_euclid(len) as usize;
+    }
+}
@@ -1,35 +1,216 @@
 //! Draws the GUI.
 
+use image::ImageResult;
+use image::{DynamicImage, ImageFormat};
 use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::{MouseButton, MouseWheelDirection};
+use sdl2::pixels::Color;
 use sdl2::rect::{Point, Rect};
-use sdl2::render::{Canvas, Texture};
+use sdl2::render::{BlendMode, Canvas};
 use sdl2::video::Window;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crate::config::{Action, Config, KeyMap};
+use super::animation::Animation;
+use super::cache::{Cache, CacheKey};
+use super::decode::{Decoded, Pixels};
+use super::downscale::Downscaler;
+use super::loader::Loader;
+use super::orientation::Orientation;
+use super::prompt::{self, Prompt};
+use super::text::{self, TextRenderer};
+use super::texture::{TextureCreationError, TextureFactory, TiledTexture};
+use crate::config::{
+    Action, BoundAction, Config, Key, KeyBind, KeyMap, Modifiers, MouseAction, MouseBind,
+    MouseInput, OsdPosition, ScaleFilter, StatusBarPosition, ZoomMode,
+};
+use crate::imagelist::ImageList;
+use crate::meta;
+
+/// Minimum size of the window when it is first fitted to an image.
+///
+/// This is also the size of the placeholders shown for images that are loading or failed to load.
+pub const MIN_SIZE: (u32, u32) = (256, 256);
+
+/// Largest width or height the scaled image may have, in pixels.
+///
+/// This keeps the image's bounding box well inside the range a [`Rect`] can represent, no matter
+/// what the configured maximum scale is.
+const MAX_EXTENT: f32 = (1 << 24) as f32;
+
+/// Number of bytes in a mebibyte.
+const MIB: usize = 1024 * 1024;
+
+/// Longest time a message is shown for, whatever the config says.
+///
+/// Adding a huge duration to the current time would overflow [`Instant`].
+const MAX_MESSAGE_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Largest count which can be typed with the digit keys.
+///
+/// Commands are repeated this many times at most, so a mistyped count can't hang the viewer.
+const MAX_COUNT: u32 = 9999;
+
+/// The digit keys which build a count, in order from 0 to 9.
+const DIGIT_KEYS: [Keycode; 10] = [
+    Keycode::Num0,
+    Keycode::Num1,
+    Keycode::Num2,
+    Keycode::Num3,
+    Keycode::Num4,
+    Keycode::Num5,
+    Keycode::Num6,
+    Keycode::Num7,
+    Keycode::Num8,
+    Keycode::Num9,
+];
+
+/// Distance in pixels the mouse must be dragged to zoom as far as one notch of the mouse wheel.
+const ZOOM_DRAG_DISTANCE: f32 = 32.0;
+
+/// The thing being displayed in place of the current image.
+enum Slide<'a> {
+    /// The image was loaded successfully.
+    ///
+    /// If high-quality downscaling is turned on, the decoded image is kept as well, as the source
+    /// for downscaled copies.
+    Image {
+        texture: TiledTexture<'a>,
+        source: Option<Arc<DynamicImage>>,
+        format: Option<ImageFormat>,
+    },
+    /// The image is animated, and was loaded successfully.
+    Animation {
+        animation: Animation<'a>,
+        format: Option<ImageFormat>,
+    },
+    /// The image is still being decoded.
+    Loading,
+    /// The image failed to load. Holds the error message.
+    Error(String),
+}
+
+impl<'a> Slide<'a> {
+    /// Approximate size of the slide's textures in bytes, if it has any.
+    fn byte_size(&self) -> Option<usize> {
+        match self {
+            Self::Image {
+                texture, source, ..
+            } => {
+                let source_size = source.as_ref().map_or(0, |image| image.as_bytes().len());
+                Some(texture.byte_size() + source_size)
+            }
+            Self::Animation { animation, .. } => {
+                Some(animation.textures().map(TiledTexture::byte_size).sum())
+            }
+            Self::Loading | Self::Error(_) => None,
+        }
+    }
+
+    /// The texture to draw, if there is one.
+    fn texture(&self) -> Option<&TiledTexture<'a>> {
+        match self {
+            Self::Image { texture, .. } => Some(texture),
+            Self::Animation { animation, .. } => Some(animation.texture()),
+            Self::Loading | Self::Error(_) => None,
+        }
+    }
+
+    /// Format of the image's file, as detected when it was decoded.
+    fn format(&self) -> Option<ImageFormat> {
+        match self {
+            Self::Image { format, .. } | Self::Animation { format, .. } => *format,
+            Self::Loading | Self::Error(_) => None,
+        }
+    }
+}
+
+/// A mouse drag in progress.
+#[derive(Clone, Copy, Debug)]
+struct Drag {
+    /// The button being held down.
+    button: MouseButton,
+    /// What dragging does.
+    action: MouseAction,
+    /// Where the button was pressed, in window coordinates.
+    origin: Point,
+}
 
 /// The app view. This is what handles all of the drawing of things to the screen.
 pub struct AppView<'a> {
     canvas: &'a mut Canvas<Window>,
-    config: &'a Config,
+    config: Config,
+    texture_factory: &'a TextureFactory,
+    loader: Loader,
+    text: TextRenderer<'a>,
 
-    /// How many times we've zoomed in/out.
+    /// The images being viewed.
+    images: ImageList,
+    /// Whether directories are searched recursively, as set by the `--recursive` option.
+    ///
+    /// The `--skip-broken` option is merged into the config instead.
+    recursive: bool,
+    /// Decoded images waiting to be uploaded, such as prefetched neighbors of the current image.
     ///
-    /// 0 means 1:1 scale, -N means zoom out N times, +N means zoom in N times.
-    zoom_level: i32,
-    /// The factor to zoom in by.
+    /// Images which failed to decode are kept as well, so they aren't decoded again.
+    decoded: Cache<ImageResult<Decoded>>,
+    /// Uploaded images which have been displayed before.
+    slides: Cache<Slide<'a>>,
+    /// Cache key of the displayed image, if it was loaded successfully.
+    slide_key: Option<CacheKey>,
+    /// Cache keys of the current image and its neighbors, with the current image first.
     ///
-    /// For example, a zoom factor of 2 means each zoom level doubles the size of the image when
-    /// zooming in, and halves it when zooming out.
-    zoom_factor: f32,
+    /// Making a key reads the file's metadata, so they are only made once each time the user
+    /// moves to another image.
+    wanted: Vec<CacheKey>,
+    /// Size of the current image's file in bytes, if it could be read.
+    file_size: Option<u64>,
+    /// Whether the user last moved towards the start of the image list.
+    backward: bool,
+    /// If true, the window should be fitted to the next image which finishes loading.
+    fit_window: bool,
+
+    /// Scale the image is displayed at. 1.0 means 1:1 scale.
+    scale: f32,
+    /// The active zoom mode, which sets the scale based on the window size.
+    ///
+    /// This is `None` if the image has been zoomed manually.
+    zoom_mode: Option<ZoomMode>,
     /// Position of the image.
     ///
     /// This position is relative to the center of the window. Therefore this point lies at the
     /// *center* of the image, **NOT** the top left corner!
     image_position: Point,
     /// Bounding-box of the image, relative to [`Self::image_position`].
+    ///
+    /// This is the unscaled size of the image, before any rotation is applied.
     image_rect: Rect,
-    /// An SDL2 texture which holds the image data.
-    image_texture: Texture<'a>,
+    /// Rotation and mirroring of the image.
+    orientation: Orientation,
+    /// If false, images are always scaled with nearest-neighbor filtering.
+    smoothing: bool,
+    /// Whether the pixel grid overlay is shown when zoomed in far enough.
+    pixel_grid: bool,
+    /// Whether the on-screen display is shown.
+    osd: bool,
+    /// A transient message shown on the OSD, and when it should disappear.
+    message: Option<(String, Instant)>,
+    /// Whether the keybinding help overlay is shown.
+    help: bool,
+    /// The command line, if it's open.
+    prompt: Option<Prompt>,
+    /// Count typed with the digit keys, which the next keybind repeats its action by.
+    count: Option<u32>,
+    /// The mouse drag in progress, if any.
+    drag: Option<Drag>,
+    /// Makes high-quality downscaled copies of the image.
+    downscaler: Downscaler<'a>,
+    /// The current image, or an error if it couldn't be loaded.
+    slide: Slide<'a>,
 
     /// If true, the view has changed and the window should be redrawn.
     dirty: bool,
@@ -38,29 +219,56 @@ pub struct AppView<'a> {
 }
 
 impl<'a> AppView<'a> {
-    /// Construct a new AppView.
+    /// Construct a new AppView, displaying the current image of the list.
+    ///
+    /// Once the image has loaded, the window is resized to fit it, within the bounds of the
+    /// screen.
     pub fn new(
         canvas: &'a mut Canvas<Window>,
-        config: &'a Config,
-        image_texture: Texture<'a>,
-    ) -> Self {
-        let t = image_texture.query();
-        Self {
+        config: &Config,
+        texture_factory: &'a TextureFactory,
+        loader: Loader,
+        images: ImageList,
+        recursive: bool,
+    ) -> Result<Self, Box<dyn Error>> {
+        if images.is_empty() {
+            return Err("no images to display".into());
+        }
+        let mut view = Self {
             canvas,
-            config,
-            zoom_level: 0,
-            zoom_factor: 2.0,
+            config: config.clone(),
+            texture_factory,
+            loader,
+            text: TextRenderer::new(texture_factory)?,
+            images,
+            recursive,
+            decoded: Cache::new(config.cache.decoded_size.saturating_mul(MIB)),
+            slides: Cache::new(config.cache.texture_size.saturating_mul(MIB)),
+            slide_key: None,
+            wanted: vec![],
+            file_size: None,
+            backward: false,
+            fit_window: true,
+            scale: 1.0,
+            zoom_mode: None,
             image_position: Point::new(0, 0),
-            image_rect: Rect::new(
-                -((t.width / 2) as i32),
-                -((t.height / 2) as i32),
-                t.width,
-                t.height,
-            ),
-            image_texture,
+            image_rect: Rect::new(0, 0, 1, 1),
+            orientation: Orientation::default(),
+            smoothing: true,
+            pixel_grid: config.pixel_grid.enabled,
+            osd: config.osd.enabled,
+            message: None,
+            help: false,
+            prompt: None,
+            count: None,
+            drag: None,
+            downscaler: Downscaler::new(),
+            slide: Slide::Error(String::new()),
             dirty: true,
             is_running: true,
-        }
+        };
+        view.open_current();
+        Ok(view)
     }
 
     /// Check if the app is running or terminated.
@@ -75,14 +283,68 @@ impl<'a> AppView<'a> {
         self.dirty.then(|| self.do_draw());
     }
 
+    /// When the view next needs to be updated, even if no events occur in the meantime.
+    ///
+    /// `None` means the view is idle, and the caller can wait for events indefinitely.
+    pub fn next_wakeup(&self) -> Option<Instant> {
+        let animation = match &self.slide {
+            Slide::Animation { animation, .. } => animation.next_wakeup(),
+            _ => None,
+        };
+        let message = self.message.as_ref().map(|(_, expiry)| *expiry);
+        [animation, self.downscaler.next_wakeup(), message]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+
+    /// Update any time-based state of the view, and pick up images which finished loading.
+    ///
+    /// This should be called after handling events, and whenever the deadline returned by
+    /// [`Self::next_wakeup`] passes. This function may set the dirty bit.
+    pub fn update(&mut self, now: Instant) {
+        self.collect_loaded();
+        if self
+            .message
+            .as_ref()
+            .is_some_and(|(_, expiry)| *expiry <= now)
+        {
+            self.message = None;
+            self.dirty = true;
+        }
+        // The copy is filtered, so it's not wanted while smoothing is turned off.
+        let wanted_copy = (self.smoothing && self.scale < self.config.scaling.downscale_threshold)
+            .then(|| self.display_size());
+        match &mut self.slide {
+            Slide::Animation { animation, .. } => self.dirty |= animation.update(now),
+            Slide::Image {
+                source: Some(source),
+                ..
+            } => {
+                self.downscaler.want(wanted_copy, now);
+                self.downscaler.update(now, source, &mut self.loader);
+            }
+            _ => (),
+        }
+    }
+
     /// Handle an SDL2 input event.
     ///
-    /// If a key which is bound to an action is pressed, that action will be executed.
+    /// If a key or mouse input which is bound to an action is used, that action will be executed.
     /// If a quit event occurs, the `is_running` flag will be set to `false`.
     /// This function may set the dirty bit.
     pub fn handle_event(&mut self, event: &Event) {
-        if let Some(c) = Command::try_from_event_using_keymap(event, &self.config.binds) {
+        if (self.prompt.is_some() && self.prompt_event(event)) || self.count_event(event) {
+            return;
+        }
+        if let Some(c) = self.mouse_command(event) {
+            // Counts only apply to keybinds, but any other command uses them up, so they can't
+            // linger until an unrelated key is pressed.
+            self.take_count();
             self.do_command(&c);
+        } else if let Some(c) = Command::try_from_event_using_keymap(event, &self.config.binds) {
+            let count = self.take_count();
+            self.do_command_repeatedly(&c, count);
         } else {
             match event {
                 Event::Quit { .. } => self.is_running = false,
@@ -92,6 +354,11 @@ impl<'a> AppView<'a> {
                     ..
                 } => self.dirty = true,
 
+                Event::Window {
+                    win_event: WindowEvent::SizeChanged(..),
+                    ..
+                } => self.window_resized(),
+
                 _ => (),
             }
         }
@@ -101,6 +368,16 @@ impl<'a> AppView<'a> {
     pub fn do_command(&mut self, command: &Command) {
         match command {
             Command::Translate(xrel, yrel) => self.translate(*xrel, *yrel),
+            Command::ZoomAt { steps, x, y } => {
+                let anchor = self.window_to_view(*x, *y);
+                self.zoom_at(self.config.zoom.scroll_factor.powf(*steps), anchor);
+            }
+            Command::Zoom(factor) => self.zoom_at(*factor, Point::new(0, 0)),
+            Command::ZoomTo(scale) => self.zoom_to(*scale, Point::new(0, 0)),
+            Command::Rotate(quarter_turns) => self.rotate(*quarter_turns),
+            Command::GoTo(index) => self.go_to(*index),
+            Command::Open(path) => self.open_path(path),
+            Command::Set(name, value) => self.set_option(name, value),
             Command::Wrap(a) => match a {
                 Action::ShiftUp => self.translate(0, -i32::from(self.config.shift_amount)),
                 Action::ShiftDown => self.translate(0, self.config.shift_amount.into()),
@@ -109,27 +386,777 @@ impl<'a> AppView<'a> {
                 Action::ResetTransform => self.reset_transform(),
                 Action::ZoomIn => self.zoom_in(),
                 Action::ZoomOut => self.zoom_out(),
+                Action::ActualSize => self.set_zoom_mode(ZoomMode::ActualSize),
+                Action::FitWindow => self.set_zoom_mode(ZoomMode::FitWindow),
+                Action::FitWidth => self.set_zoom_mode(ZoomMode::FitWidth),
+                Action::FitHeight => self.set_zoom_mode(ZoomMode::FitHeight),
+                Action::Fill => self.set_zoom_mode(ZoomMode::Fill),
+                Action::RotateClockwise => self.rotate(1),
+                Action::RotateCounterClockwise => self.rotate(-1),
+                Action::FlipHorizontal => self.flip(Orientation::flip_horizontal),
+                Action::FlipVertical => self.flip(Orientation::flip_vertical),
+                Action::ToggleSmoothing => self.toggle_smoothing(),
+                Action::TogglePixelGrid => self.toggle_pixel_grid(),
+                Action::ToggleOsd => self.toggle_osd(),
+                Action::ShowHelp => self.toggle_help(),
+                Action::CommandPrompt => self.open_prompt(),
+                Action::TogglePlayback => self.toggle_playback(),
+                Action::NextFrame => self.step_animation(1),
+                Action::PrevFrame => self.step_animation(-1),
                 Action::ToggleFullscreen => self.toggle_fullscreen(),
+                Action::NextImage => self.navigate(ImageList::go_next, false),
+                Action::PrevImage => self.navigate(ImageList::go_prev, true),
+                Action::FirstImage => self.navigate(ImageList::go_first, false),
+                Action::LastImage => self.navigate(ImageList::go_last, true),
                 Action::Quit => self.quit(),
             },
         };
     }
 
+    /// Execute a viewer command `count` times.
+    ///
+    /// Only commands where repetition means something are repeated, such as shifts and zoom
+    /// steps; the count is ignored for anything else. Moving through the image list jumps
+    /// straight to the destination, rather than opening every image along the way.
+    fn do_command_repeatedly(&mut self, command: &Command, count: u32) {
+        let count = count as usize;
+        match command {
+            _ if !command.is_repeatable() => self.do_command(command),
+            Command::Wrap(Action::NextImage) if count > 1 => {
+                let last = self.images.len().saturating_sub(1);
+                self.go_to(self.images.index().saturating_add(count).min(last));
+            }
+            Command::Wrap(Action::PrevImage) if count > 1 => {
+                self.go_to(self.images.index().saturating_sub(count));
+            }
+            _ => {
+                for _ in 0..count {
+                    self.do_command(command);
+                }
+            }
+        }
+    }
+
+    /// Handle a digit key pressed to build a count, if counts are enabled.
+    ///
+    /// Returns `true` if the event was used by the count. A count can't start with 0, so 0 can
+    /// still be bound, and Escape cancels the count.
+    fn count_event(&mut self, event: &Event) -> bool {
+        let Event::KeyDown {
+            keycode: Some(keycode),
+            keymod,
+            ..
+        } = event
+        else {
+            return false;
+        };
+        if !self.config.count_prefix || Modifiers::from(*keymod) != Modifiers::default() {
+            return false;
+        }
+        if *keycode == Keycode::Escape && self.count.is_some() {
+            self.take_count();
+            return true;
+        }
+        // Only the digits in the number row count, so the keypad can still be bound.
+        let digit = match DIGIT_KEYS.iter().position(|key| key == keycode) {
+            Some(digit) if digit > 0 || self.count.is_some() => digit as u32,
+            _ => return false,
+        };
+        let count = self
+            .count
+            .unwrap_or(0)
+            .saturating_mul(10)
+            .saturating_add(digit)
+            .min(MAX_COUNT);
+        self.count = Some(count);
+        self.show_message(format!("Count: {count}"));
+        true
+    }
+
+    /// Clear the count typed with the digit keys, returning it, or 1 if there was none.
+    fn take_count(&mut self) -> u32 {
+        let Some(count) = self.count.take() else {
+            return 1;
+        };
+        // The count's message is no longer accurate.
+        self.message = None;
+        self.dirty = true;
+        count
+    }
+
+    /// The modifier keys which are currently held down.
+    ///
+    /// Mouse events don't say which modifiers are held, unlike keyboard events, so this asks SDL.
+    fn held_modifiers(&self) -> Modifiers {
+        let sdl = self.canvas.window().subsystem().sdl();
+        sdl.keyboard().mod_state().into()
+    }
+
+    /// Translate a mouse event into a command, using the mouse binds.
+    ///
+    /// Pressing a button bound to a drag behavior starts a drag, which lasts until the button is
+    /// released. Double-clicks trigger the double-click bind instead of the single-click one, if
+    /// there is one.
+    fn mouse_command(&mut self, event: &Event) -> Option<Command> {
+        let modifiers = self.held_modifiers();
+        let binds = &self.config.mouse;
+        let bound = |input| binds.get(&MouseBind::new(input, modifiers)).copied();
+        match event {
+            Event::MouseButtonDown {
+                mouse_btn,
+                clicks,
+                x,
+                y,
+                ..
+            } => {
+                let double = (clicks % 2 == 0)
+                    .then(|| bound(MouseInput::DoubleClick(*mouse_btn)))
+                    .flatten();
+                match double.or_else(|| bound(MouseInput::Button(*mouse_btn)))? {
+                    MouseAction::Action(action) => Some(action.into()),
+                    MouseAction::Zoom => None,
+                    action => {
+                        self.drag = Some(Drag {
+                            button: *mouse_btn,
+                            action,
+                            origin: Point::new(*x, *y),
+                        });
+                        None
+                    }
+                }
+            }
+
+            Event::MouseButtonUp { mouse_btn, .. } => {
+                if self.drag.is_some_and(|drag| drag.button == *mouse_btn) {
+                    self.drag = None;
+                }
+                None
+            }
+
+            Event::MouseMotion {
+                mousestate,
+                xrel,
+                yrel,
+                ..
+            } => {
+                // The button may have been released outside the window.
+                let drag = self
+                    .drag
+                    .filter(|drag| mousestate.is_mouse_button_pressed(drag.button));
+                self.drag = drag;
+                match drag?.action {
+                    MouseAction::Pan => Some(Command::Translate(*xrel, *yrel)),
+                    MouseAction::ZoomDrag => Some(Command::ZoomAt {
+                        steps: -*yrel as f32 / ZOOM_DRAG_DISTANCE,
+                        x: drag?.origin.x(),
+                        y: drag?.origin.y(),
+                    }),
+                    _ => None,
+                }
+            }
+
+            Event::MouseWheel {
+                x,
+                y,
+                precise_x,
+                precise_y,
+                direction,
+                mouse_x,
+                mouse_y,
+                ..
+            } => {
+                // Prefer the precise values for smooth scrolling, if SDL is new enough to report
+                // them.
+                let precise = |precise: f32, coarse: i32| {
+                    let steps = if precise != 0.0 {
+                        precise
+                    } else {
+                        coarse as f32
+                    };
+                    match direction {
+                        MouseWheelDirection::Flipped => -steps,
+                        _ => steps,
+                    }
+                };
+                let (steps, input) = match (precise(*precise_x, *x), precise(*precise_y, *y)) {
+                    (_, dy) if dy > 0.0 => (dy, MouseInput::WheelUp),
+                    (_, dy) if dy < 0.0 => (dy, MouseInput::WheelDown),
+                    (dx, _) if dx > 0.0 => (dx, MouseInput::WheelRight),
+                    (dx, _) if dx < 0.0 => (dx, MouseInput::WheelLeft),
+                    _ => return None,
+                };
+                match bound(input)? {
+                    MouseAction::Zoom => Some(Command::ZoomAt {
+                        steps,
+                        x: *mouse_x,
+                        y: *mouse_y,
+                    }),
+                    MouseAction::Action(action) => Some(action.into()),
+                    _ => None,
+                }
+            }
+
+            _ => None,
+        }
+    }
+
+    /// Handle an event while the command line is open.
+    ///
+    /// Returns `true` if the event was used by the command line. Keyboard events are never passed
+    /// on to the keybinds while it's open.
+    fn prompt_event(&mut self, event: &Event) -> bool {
+        let Some(prompt) = &mut self.prompt else {
+            return false;
+        };
+        match event {
+            Event::TextInput { text, .. } => prompt.insert(text),
+            Event::KeyDown {
+                keycode: Some(keycode),
+                ..
+            } => match *keycode {
+                Keycode::Escape => {
+                    self.close_prompt();
+                }
+                Keycode::Return | Keycode::KpEnter => self.run_prompt(),
+                // Deleting past the start of the line closes it, like in vim.
+                Keycode::Backspace if !prompt.backspace() => {
+                    self.close_prompt();
+                }
+                Keycode::Tab => prompt.complete(),
+                _ => (),
+            },
+            Event::KeyDown { .. } | Event::KeyUp { .. } => (),
+            _ => return false,
+        }
+        self.dirty = true;
+        true
+    }
+
+    /// Open the command line, and start accepting text input.
+    fn open_prompt(&mut self) {
+        self.prompt = Some(Prompt::default());
+        self.canvas.window().subsystem().text_input().start();
+        self.dirty = true;
+    }
+
+    /// Close the command line, returning what was typed into it.
+    fn close_prompt(&mut self) -> Option<Prompt> {
+        self.canvas.window().subsystem().text_input().stop();
+        self.dirty = true;
+        self.prompt.take()
+    }
+
+    /// Close the command line and run the command typed into it.
+    ///
+    /// If the command can't be parsed, the error is shown on the OSD.
+    fn run_prompt(&mut self) {
+        let Some(prompt) = self.close_prompt() else {
+            return;
+        };
+        self.take_count();
+        if prompt.input().trim().is_empty() {
+            return;
+        }
+        match prompt::parse(prompt.input()) {
+            Ok(command) => self.do_command(&command),
+            Err(err) => self.show_message(err),
+        }
+    }
+
+    // --- Image Loading --- ///////////////////////////////
+
+    /// Upload a decoded image into textures.
+    fn upload(&self, decoded: Decoded) -> Result<Slide<'a>, TextureCreationError> {
+        let format = decoded.format;
+        Ok(match decoded.pixels {
+            Pixels::Still(image) => {
+                let texture = self.texture_factory.construct_from_image(&image)?;
+                let source = self
+                    .config
+                    .scaling
+                    .high_quality_downscale
+                    .then(|| Arc::new(image));
+                Slide::Image {
+                    texture,
+                    source,
+                    format,
+                }
+            }
+            Pixels::Animated(frames) => {
+                let textures = frames
+                    .into_iter()
+                    .map(|(frame, delay)| {
+                        let image = DynamicImage::ImageRgba8(frame);
+                        Ok((self.texture_factory.construct_from_image(&image)?, delay))
+                    })
+                    .collect::<Result<Vec<_>, TextureCreationError>>()?;
+                Slide::Animation {
+                    animation: Animation::new(textures),
+                    format,
+                }
+            }
+        })
+    }
+
+    /// Replace the displayed image with the current image of the list.
+    ///
+    /// The old image is moved into the cache. If the new image is cached, or has already been
+    /// prefetched, it's displayed right away. Otherwise, a loading indicator is shown until it
+    /// finishes decoding.
+    fn open_current(&mut self) {
+        self.stash_slide();
+        self.wanted = [
+            self.images.current(),
+            self.images.peek(1),
+            self.images.peek(-1),
+        ]
+        .into_iter()
+        .flatten()
+        .map(CacheKey::new)
+        .collect();
+        let Some(key) = self.wanted.first().cloned() else {
+            return;
+        };
+        if let Some(slide) = self.slides.take(&key) {
+            self.slide = slide;
+            self.slide_key = Some(key);
+            self.reset_image();
+        } else if let Some(result) = self.decoded.take(&key) {
+            self.show(result);
+        } else {
+            self.slide = Slide::Loading;
+            self.reset_image();
+        }
+        self.request_images();
+    }
+
+    /// Move the displayed image into the cache, leaving a loading indicator in its place.
+    fn stash_slide(&mut self) {
+        let slide = std::mem::replace(&mut self.slide, Slide::Loading);
+        if let (Some(key), Some(size)) = (self.slide_key.take(), slide.byte_size()) {
+            self.slides.insert(key, slide, size);
+        }
+    }
+
+    /// Display the result of decoding the current image.
+    ///
+    /// If the image failed to load, an error placeholder is displayed instead. However, if the
+    /// `skip_broken` setting is on, the image is dropped from the list and the next one is opened,
+    /// continuing in the direction the user was moving.
+    fn show(&mut self, result: ImageResult<Decoded>) {
+        let path = self.images.current().unwrap_or(Path::new("")).to_path_buf();
+        let slide = result
+            .map_err(Box::<dyn Error>::from)
+            .and_then(|decoded| Ok(self.upload(decoded)?));
+        match slide {
+            Ok(slide) => {
+                self.slide = slide;
+                self.slide_key = self.wanted.first().cloned();
+            }
+            Err(err) => {
+                log::error!("failed to open \"{}\": {err}", path.display());
+                if self.config.skip_broken && self.images.len() > 1 {
+                    self.images.remove_current(self.backward);
+                    self.open_current();
+                    return;
+                }
+                self.slide = Slide::Error(err.to_string());
+            }
+        }
+        self.reset_image();
+        if std::mem::take(&mut self.fit_window) {
+            self.fit_window_to_image();
+        }
+    }
+
+    /// Ask the loader for the current image, if it isn't loaded yet, and its neighbors.
+    ///
+    /// Images which are already cached are not requested again.
+    fn request_images(&mut self) {
+        let skip_current = !matches!(self.slide, Slide::Loading);
+        let wanted = self
+            .wanted
+            .iter()
+            .skip(usize::from(skip_current))
+            .filter(|key| !self.decoded.contains(key) && !self.slides.contains(key))
+            .map(CacheKey::path)
+            .collect::<Vec<_>>();
+        self.loader.request(&wanted);
+    }
+
+    /// Collect images which the loader has finished decoding, and downscaled copies it has made.
+    ///
+    /// The current image is displayed, and anything else is cached for later. Failed prefetches
+    /// are cached too, so they aren't decoded again unless the file changes.
+    fn collect_loaded(&mut self) {
+        for (path, result) in self.loader.poll() {
+            if self.images.current() == Some(path.as_path()) {
+                if matches!(self.slide, Slide::Loading) {
+                    self.show(result);
+                    self.request_images();
+                }
+                continue;
+            }
+            let key = self
+                .wanted
+                .iter()
+                .find(|key| key.path() == path)
+                .cloned()
+                .unwrap_or_else(|| CacheKey::new(&path));
+            // Errors take next to no memory, so they don't count towards the budget.
+            let size = result.as_ref().map_or(0, Decoded::byte_size);
+            self.decoded.insert(key, result, size);
+        }
+        for downscaled in self.loader.poll_downscaled() {
+            self.dirty |= self.downscaler.finish(downscaled, self.texture_factory);
+        }
+    }
+
+    /// Resize the window to fit the image, but don't let it grow larger than the screen.
+    ///
+    /// Room is left for the status bar, if it's shown.
+    fn fit_window_to_image(&mut self) {
+        let (mut w, mut h) = self.image_rect.size();
+        h += self.status_bar_height();
+        let window = self.canvas.window_mut();
+        if let Ok(bounds) = window
+            .display_index()
+            .and_then(|i| window.subsystem().display_usable_bounds(i))
+        {
+            w = w.min(bounds.width());
+            h = h.min(bounds.height());
+        }
+        let _ = window.set_size(w.max(MIN_SIZE.0), h.max(MIN_SIZE.1));
+    }
+
+    /// Update the view state to match a freshly loaded image.
+    fn reset_image(&mut self) {
+        let (width, height) = self.slide.texture().map_or(MIN_SIZE, TiledTexture::size);
+        self.downscaler.clear();
+        self.image_rect = Rect::new(-((width / 2) as i32), -((height / 2) as i32), width, height);
+        self.file_size = self
+            .images
+            .current()
+            .and_then(|path| path.metadata().ok())
+            .map(|metadata| metadata.len());
+        if let Some(path) = self.images.current() {
+            let title = match &self.slide {
+                Slide::Loading => format!("{} - {} (loading)", meta::NAME, path.display()),
+                Slide::Error(err) => format!("{} - {} ({err})", meta::NAME, path.display()),
+                _ => format!("{} - {}", meta::NAME, path.display()),
+            };
+            let _ = self.canvas.window_mut().set_title(&title);
+        }
+        self.reset_transform();
+    }
+
     // --- Drawing --- /////////////////////////////////////
 
     fn do_draw(&mut self) {
-        let (ow, oh) = self.canvas.output_size().unwrap();
+        let center = self.viewport().center();
         let rect = self
             .transformed_rect()
-            .right_shifted(ow as i32 / 2)
-            .bottom_shifted(oh as i32 / 2);
+            .right_shifted(center.x())
+            .bottom_shifted(center.y());
 
         self.draw_background();
-        let _ = self.canvas.copy(&self.image_texture, None, Some(rect));
+        match &self.slide {
+            Slide::Loading => self.draw_loading_placeholder(rect),
+            Slide::Error(err) => {
+                let err = err.clone();
+                self.draw_error_placeholder(rect, &err);
+            }
+            slide => {
+                // The texture is rotated around the center of the destination rect, so the
+                // destination is the unrotated rect sharing a center with the bounding box.
+                let (w, h) = self.display_size();
+                let dst = Rect::from_center(rect.center(), w, h);
+                let (flip_h, flip_v) = self.orientation.flips();
+                let angle = self.orientation.angle();
+
+                // Prefer a high-quality downscaled copy, if one is ready for this size, unless
+                // smoothing is turned off.
+                let copy = self.downscaler.get((w, h)).filter(|_| self.smoothing);
+                let texture = match copy {
+                    Some(copy) => copy,
+                    None => slide.texture().expect("slide should have a texture"),
+                };
+                texture.set_scale_filter(self.scale_filter());
+                let _ = texture.draw(self.canvas, dst, angle, flip_h, flip_v);
+
+                if self.pixel_grid && self.scale >= self.config.pixel_grid.threshold {
+                    self.draw_pixel_grid(rect);
+                }
+            }
+        }
+        self.draw_status_bar();
+        self.draw_osd();
+        if self.help {
+            self.draw_help();
+        }
+        if let Some(prompt) = &self.prompt {
+            let line = format!(":{}_", prompt.input());
+            self.draw_prompt(&line);
+        }
         self.canvas.present();
         self.dirty = false;
     }
 
+    /// Draw lines along the pixel boundaries of the image, whose bounding box is `rect`.
+    ///
+    /// Only the lines which are inside the window are drawn.
+    fn draw_pixel_grid(&mut self, rect: Rect) {
+        let (ow, oh) = self.canvas.output_size().unwrap();
+        let (columns, rows) = self.oriented_size();
+        let step_x = f64::from(rect.width()) / f64::from(columns.max(1));
+        let step_y = f64::from(rect.height()) / f64::from(rows.max(1));
+
+        // Boundary positions, in window coordinates, of the lines which fall inside `0..limit`.
+        let boundaries = |start: i32, step: f64, count: u32, limit: u32| {
+            let first = (f64::from(-start) / step).floor().max(0.0) as u32;
+            let last = ((f64::from(limit as i32 - start) / step).ceil() as u32).min(count);
+            (first..=last).map(move |i| start + (f64::from(i) * step).round() as i32)
+        };
+
+        let top = rect.top().max(0);
+        let bottom = rect.bottom().min(oh as i32);
+        let left = rect.left().max(0);
+        let right = rect.right().min(ow as i32);
+        let mut lines = vec![];
+        for x in boundaries(rect.left(), step_x, columns, ow) {
+            lines.push((Point::new(x, top), Point::new(x, bottom)));
+        }
+        for y in boundaries(rect.top(), step_y, rows, oh) {
+            lines.push((Point::new(left, y), Point::new(right, y)));
+        }
+
+        let (r, g, b) = self.config.pixel_grid.color.as_rgb();
+        let a = (self.config.pixel_grid.opacity.clamp(0.0, 1.0) * 255.0).round() as u8;
+        self.canvas.set_blend_mode(BlendMode::Blend);
+        self.canvas.set_draw_color(Color::RGBA(r, g, b, a));
+        for (start, end) in lines {
+            let _ = self.canvas.draw_line(start, end);
+        }
+        self.canvas.set_blend_mode(BlendMode::None);
+    }
+
+    /// Draw the on-screen display, along with any transient message, in its configured corner.
+    fn draw_osd(&mut self) {
+        let osd = &self.config.osd;
+        let mut lines = vec![];
+        if self.osd {
+            lines.push(text::expand_template(&osd.template, |name| {
+                self.placeholder(name)
+            }));
+        }
+        if let Some((message, _)) = &self.message {
+            lines.push(message.clone());
+        }
+        let content = lines.join("\n");
+        if content.is_empty() {
+            return;
+        }
+
+        let scale = osd.font_scale.max(1);
+        let padding = text::GLYPH_SIZE / 2 * scale;
+        let (w, h) = text::measure(&content, scale);
+        let (box_w, box_h) = (w + padding * 2, h + padding * 2);
+        // Keep clear of the status bar.
+        let viewport = self.viewport();
+        let left = viewport.left() + padding as i32;
+        let top = viewport.top() + padding as i32;
+        let right = viewport.right() - (box_w + padding) as i32;
+        let bottom = viewport.bottom() - (box_h + padding) as i32;
+        let (x, y) = match osd.position {
+            OsdPosition::TopLeft => (left, top),
+            OsdPosition::TopRight => (right, top),
+            OsdPosition::BottomLeft => (left, bottom),
+            OsdPosition::BottomRight => (right, bottom),
+        };
+
+        let (r, g, b) = osd.background.as_rgb();
+        let a = (osd.background_opacity.clamp(0.0, 1.0) * 255.0).round() as u8;
+        self.canvas.set_blend_mode(BlendMode::Blend);
+        self.canvas.set_draw_color(Color::RGBA(r, g, b, a));
+        let _ = self.canvas.fill_rect(Rect::new(x, y, box_w, box_h));
+        self.canvas.set_blend_mode(BlendMode::None);
+
+        let (r, g, b) = osd.color.as_rgb();
+        let position = Point::new(x + padding as i32, y + padding as i32);
+        let _ = self
+            .text
+            .draw(self.canvas, &content, position, scale, Color::RGB(r, g, b));
+    }
+
+    /// Draw a list of every action and the keys bound to it over the middle of the viewport.
+    ///
+    /// Actions with parameters are listed after the basic actions, if any keys are bound to them.
+    /// If the list doesn't fit, the font is scaled down, and then the list is split into columns.
+    fn draw_help(&mut self) {
+        let binds = &self.config.binds;
+        let parameterized = binds
+            .iter()
+            .filter(|(_, action)| !matches!(action, BoundAction::Action(_)))
+            .map(|(_, action)| (action.to_string(), *action))
+            .collect::<BTreeMap<_, _>>();
+        let actions = enum_iterator::all::<Action>()
+            .map(|action| (format!("{action:?}"), BoundAction::Action(action)))
+            .chain(parameterized)
+            .collect::<Vec<_>>();
+        let width = actions
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or(0);
+        let lines = actions
+            .iter()
+            .map(|(name, action)| {
+                let keys = binds.keys_for(*action);
+                let keys = if keys.is_empty() {
+                    "-".to_string()
+                } else {
+                    keys.iter()
+                        .map(|key| key.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                format!("{name:<width$}  {keys}")
+            })
+            .collect::<Vec<_>>();
+
+        let viewport = self.viewport();
+        let line_height = |scale: u32| text::GLYPH_SIZE * scale;
+        let mut scale = self.config.osd.font_scale.max(1);
+        // Leave room for a line of padding above and below.
+        while scale > 1 && line_height(scale) * (lines.len() as u32 + 2) > viewport.height() {
+            scale -= 1;
+        }
+        let padding = line_height(scale);
+        let rows = (viewport.height().saturating_sub(padding * 2) / padding).max(1) as usize;
+        let columns = lines
+            .chunks(rows)
+            .map(|chunk| chunk.join("\n"))
+            .collect::<Vec<_>>();
+
+        let sizes = columns
+            .iter()
+            .map(|column| text::measure(column, scale))
+            .collect::<Vec<_>>();
+        let content_w = sizes.iter().map(|(w, _)| w + padding).sum::<u32>() - padding;
+        let content_h = sizes.iter().map(|(_, h)| *h).max().unwrap_or(0);
+        let help_box = Rect::from_center(
+            viewport.center(),
+            content_w + padding * 2,
+            content_h + padding * 2,
+        );
+
+        let osd = &self.config.osd;
+        let (r, g, b) = osd.background.as_rgb();
+        self.canvas.set_blend_mode(BlendMode::Blend);
+        self.canvas.set_draw_color(Color::RGBA(r, g, b, 0xe0));
+        let _ = self.canvas.fill_rect(help_box);
+        self.canvas.set_blend_mode(BlendMode::None);
+
+        let (r, g, b) = osd.color.as_rgb();
+        let mut x = help_box.left() + padding as i32;
+        for (column, (w, _)) in columns.iter().zip(sizes) {
+            let position = Point::new(x, help_box.top() + padding as i32);
+            let _ = self
+                .text
+                .draw(self.canvas, column, position, scale, Color::RGB(r, g, b));
+            x += (w + padding) as i32;
+        }
+    }
+
+    /// Draw the status bar along its configured edge of the window, if it's enabled.
+    fn draw_status_bar(&mut self) {
+        let height = self.status_bar_height();
+        if height == 0 {
+            return;
+        }
+        let bar = &self.config.status_bar;
+        let (ow, oh) = self.canvas.output_size().unwrap();
+        let y = match bar.position {
+            StatusBarPosition::Top => 0,
+            StatusBarPosition::Bottom => oh.saturating_sub(height) as i32,
+        };
+        self.canvas.set_draw_color(bar.background.as_rgb());
+        let _ = self.canvas.fill_rect(Rect::new(0, y, ow, height));
+
+        // The bar only has room for a single line.
+        let content =
+            text::expand_template(&bar.template, |name| self.placeholder(name)).replace('\n', " ");
+        let scale = bar.font_scale.max(1);
+        let padding = (height - text::GLYPH_SIZE * scale) as i32 / 2;
+        let (r, g, b) = bar.color.as_rgb();
+        let position = Point::new(padding, y + padding);
+        let _ = self
+            .text
+            .draw(self.canvas, &content, position, scale, Color::RGB(r, g, b));
+    }
+
+    /// Draw a line of the command line along the bottom edge of the window, in the style of the
+    /// status bar.
+    ///
+    /// If the line is too long to fit, only its end is shown.
+    fn draw_prompt(&mut self, line: &str) {
+        let bar = &self.config.status_bar;
+        let scale = bar.font_scale.max(1);
+        let height = text::GLYPH_SIZE * scale * 3 / 2;
+        let padding = (height - text::GLYPH_SIZE * scale) / 2;
+        let (ow, oh) = self.canvas.output_size().unwrap();
+        let y = oh.saturating_sub(height) as i32;
+        self.canvas.set_draw_color(bar.background.as_rgb());
+        let _ = self.canvas.fill_rect(Rect::new(0, y, ow, height));
+
+        let fits = (ow.saturating_sub(padding * 2) / (text::GLYPH_SIZE * scale)).max(1) as usize;
+        let skip = line.chars().count().saturating_sub(fits);
+        let visible = line.chars().skip(skip).collect::<String>();
+        let (r, g, b) = bar.color.as_rgb();
+        let position = Point::new(padding as i32, y + padding as i32);
+        let _ = self
+            .text
+            .draw(self.canvas, &visible, position, scale, Color::RGB(r, g, b));
+    }
+
+    /// The value of a placeholder in the OSD or status bar template, or `None` if the name isn't
+    /// recognized.
+    fn placeholder(&self, name: &str) -> Option<String> {
+        let path = self.images.current().unwrap_or(Path::new(""));
+        let size = self.slide.texture().map(TiledTexture::size);
+        let dimension =
+            |get: fn((u32, u32)) -> u32| size.map_or("?".to_string(), |s| get(s).to_string());
+        Some(match name {
+            "filename" => path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            "path" => path.display().to_string(),
+            "index" => (self.images.index() + 1).to_string(),
+            "total" => self.images.len().to_string(),
+            "width" => dimension(|(w, _)| w),
+            "height" => dimension(|(_, h)| h),
+            "zoom" => format!("{:.0}%", self.scale * 100.0),
+            "format" => self.slide.format().map_or("?".to_string(), |format| {
+                format!("{format:?}").to_uppercase()
+            }),
+            "filesize" => self.file_size.map_or("?".to_string(), format_file_size),
+            _ => return None,
+        })
+    }
+
+    /// The filter to scale the image with at the current scale.
+    fn scale_filter(&self) -> ScaleFilter {
+        if !self.smoothing {
+            ScaleFilter::Nearest
+        } else if self.scale > 1.0 {
+            self.config.scaling.zoom_in
+        } else {
+            self.config.scaling.zoom_out
+        }
+    }
+
     fn draw_background(&mut self) {
         self.canvas
             .set_draw_color(self.config.grid.color_light.as_rgb());
@@ -161,18 +1188,98 @@ impl<'a> AppView<'a> {
         let _ = self.canvas.fill_rects(rects.as_slice());
     }
 
+    /// Draw a box with a row of dots in place of an image that is still loading.
+    fn draw_loading_placeholder(&mut self, rect: Rect) {
+        self.canvas.set_draw_color(Color::RGB(0x20, 0x20, 0x20));
+        let _ = self.canvas.fill_rect(rect);
+        self.canvas.set_draw_color(Color::RGB(0xc0, 0xc0, 0xc0));
+        let _ = self.canvas.draw_rect(rect);
+        let dot = (rect.width().min(rect.height()) / 16).max(1);
+        let dots = [-2, 0, 2]
+            .map(|i| Rect::from_center(rect.center().offset(i * dot as i32, 0), dot, dot));
+        let _ = self.canvas.fill_rects(&dots);
+    }
+
+    /// Draw a crossed-out box in place of an image that failed to load.
+    ///
+    /// The error message is written across the middle of the box, wrapped to fit inside it.
+    fn draw_error_placeholder(&mut self, rect: Rect, message: &str) {
+        self.canvas.set_draw_color(Color::RGB(0x20, 0x20, 0x20));
+        let _ = self.canvas.fill_rect(rect);
+        self.canvas.set_draw_color(Color::RGB(0xc0, 0x20, 0x20));
+        let _ = self.canvas.draw_rect(rect);
+        let _ = self.canvas.draw_line(rect.top_left(), rect.bottom_right());
+        let _ = self.canvas.draw_line(rect.top_right(), rect.bottom_left());
+
+        // Leave a glyph's width of padding inside the box, and behind the text so the lines
+        // don't run through it.
+        let scale = self.config.osd.font_scale.max(1);
+        let glyph = text::GLYPH_SIZE * scale;
+        let columns = (rect.width().saturating_sub(glyph * 4) / glyph) as usize;
+        let rows = (rect.height().saturating_sub(glyph * 4) / glyph) as usize;
+        if columns == 0 || rows == 0 {
+            return;
+        }
+        let content = text::wrap(message, columns, rows);
+        let (w, h) = text::measure(&content, scale);
+        let text_box = Rect::from_center(rect.center(), w + glyph * 2, h + glyph * 2);
+        self.canvas.set_draw_color(Color::RGB(0x20, 0x20, 0x20));
+        let _ = self.canvas.fill_rect(text_box);
+        let position = Point::new(text_box.x() + glyph as i32, text_box.y() + glyph as i32);
+        let color = Color::RGB(0xc0, 0xc0, 0xc0);
+        let _ = self
+            .text
+            .draw(self.canvas, &content, position, scale, color);
+    }
+
     // --- Space Conversions --- ///////////////////////////
 
+    /// Height of the status bar in pixels, or zero if it's hidden.
+    fn status_bar_height(&self) -> u32 {
+        let bar = &self.config.status_bar;
+        if bar.enabled {
+            // A line of text, with a quarter of a line's padding above and below.
+            text::GLYPH_SIZE * bar.font_scale.max(1) * 3 / 2
+        } else {
+            0
+        }
+    }
+
+    /// The area of the canvas the image is displayed in, which is all of it but the status bar.
+    fn viewport(&self) -> Rect {
+        let (ow, oh) = self.canvas.output_size().unwrap();
+        let height = self.status_bar_height().min(oh);
+        let y = match self.config.status_bar.position {
+            StatusBarPosition::Top => height as i32,
+            StatusBarPosition::Bottom => 0,
+        };
+        Rect::new(0, y, ow.max(1), (oh - height).max(1))
+    }
+
     fn scale(&self) -> f32 {
-        self.zoom_factor.powf(self.zoom_level as f32)
+        self.scale
+    }
+
+    /// Size of the image's bounding box once its orientation is applied, but before scaling.
+    fn oriented_size(&self) -> (u32, u32) {
+        self.orientation.apply_to_size(self.image_rect.size())
     }
 
+    /// Size of the image at the current scale, before its orientation is applied.
+    fn display_size(&self) -> (u32, u32) {
+        self.orientation.apply_to_size(self.scaled_rect().size())
+    }
+
+    /// The image's bounding box at the current scale and orientation, centered on the origin.
+    ///
+    /// The size is calculated from the unscaled size, rather than scaling each edge separately,
+    /// so the box stays centered regardless of rounding.
     fn scaled_rect(&self) -> Rect {
-        Rect::new(
-            (self.image_rect.x as f32 * self.scale()) as i32,
-            (self.image_rect.y as f32 * self.scale()) as i32,
-            (self.image_rect.w as f32 * self.scale()) as u32,
-            (self.image_rect.h as f32 * self.scale()) as u32,
+        let (w, h) = self.oriented_size();
+        Rect::from_center(
+            (0, 0),
+            (w as f32 * self.scale()).round() as u32,
+            (h as f32 * self.scale()).round() as u32,
         )
     }
 
@@ -182,10 +1289,23 @@ impl<'a> AppView<'a> {
             .right_shifted(self.image_position.x())
     }
 
+    /// Convert a point in window coordinates to view coordinates.
+    ///
+    /// Window coordinates have the origin at the top-left of the window, and may differ in scale
+    /// from the canvas on high-DPI displays. View coordinates have the origin at the center of the
+    /// viewport, like [`Self::image_position`].
+    fn window_to_view(&self, x: i32, y: i32) -> Point {
+        let (ww, wh) = self.canvas.window().size();
+        let (ow, oh) = self.canvas.output_size().unwrap();
+        let x = (x as f32 * ow as f32 / ww.max(1) as f32) as i32;
+        let y = (y as f32 * oh as f32 / wh.max(1) as f32) as i32;
+        Point::new(x, y) - self.viewport().center()
+    }
+
     // --- Commands --- ////////////////////////////////////////
 
     fn translate(&mut self, dx: i32, dy: i32) {
-        let (ow, oh) = self.canvas.output_size().unwrap();
+        let (ow, oh) = self.viewport().size();
 
         let o_rect = Rect::new(ow as i32 / -2, oh as i32 / -2, ow, oh);
         let rect = self.scaled_rect();
@@ -219,19 +1339,149 @@ impl<'a> AppView<'a> {
 
     fn reset_transform(&mut self) {
         self.image_position = Point::new(0, 0);
-        self.zoom_level = 0;
+        self.orientation = Orientation::default();
+        self.set_zoom_mode(self.config.zoom.mode);
+    }
+
+    /// Rotate the image by a number of quarter turns. Positive values turn clockwise.
+    fn rotate(&mut self, quarter_turns: i32) {
+        self.orientation.rotate(quarter_turns);
+        // The bounding box may have changed shape.
+        self.apply_zoom_mode();
+    }
+
+    fn flip(&mut self, flip: fn(&mut Orientation)) {
+        flip(&mut self.orientation);
         self.dirty = true;
     }
 
+    /// Zoom in to the next preset scale, or by the zoom factor if there are no presets.
     fn zoom_in(&mut self) {
-        self.zoom_level += 1;
-        self.dirty = true;
+        let zoom = &self.config.zoom;
+        let scale = if zoom.presets.is_empty() {
+            self.scale * zoom.factor
+        } else {
+            zoom.preset_above(self.scale).unwrap_or(self.scale)
+        };
+        self.zoom_to(scale, Point::new(0, 0));
     }
+
+    /// Zoom out to the previous preset scale, or by the zoom factor if there are no presets.
     fn zoom_out(&mut self) {
-        self.zoom_level -= 1;
+        let zoom = &self.config.zoom;
+        let scale = if zoom.presets.is_empty() {
+            self.scale / zoom.factor
+        } else {
+            zoom.preset_below(self.scale).unwrap_or(self.scale)
+        };
+        self.zoom_to(scale, Point::new(0, 0));
+    }
+
+    /// Scale the image by `factor`, keeping the point `anchor` fixed in place.
+    ///
+    /// `anchor` is given in view coordinates, so `(0, 0)` zooms around the center of the window.
+    fn zoom_at(&mut self, factor: f32, anchor: Point) {
+        self.zoom_to(self.scale * factor, anchor);
+    }
+
+    /// Set the image scale, keeping the point `anchor` fixed in place.
+    fn zoom_to(&mut self, scale: f32, anchor: Point) {
+        let old_scale = self.scale;
+        self.zoom_mode = None;
+        self.scale = self.clamp_scale(scale);
+
+        // The image point under the anchor is (anchor - position) / scale. Solve for the new
+        // position which puts the same image point back under the anchor.
+        let ratio = self.scale / old_scale;
+        let offset = self.image_position - anchor;
+        self.image_position = Point::new(
+            anchor.x() + (offset.x() as f32 * ratio).round() as i32,
+            anchor.y() + (offset.y() as f32 * ratio).round() as i32,
+        );
+        self.translate(0, 0);
+    }
+
+    /// Activate a zoom mode, and scale the image accordingly.
+    fn set_zoom_mode(&mut self, mode: ZoomMode) {
+        self.zoom_mode = Some(mode);
+        self.apply_zoom_mode();
+    }
+
+    /// Recalculate the scale using the active zoom mode, if there is one.
+    fn apply_zoom_mode(&mut self) {
+        if let Some(mode) = self.zoom_mode {
+            let viewport = self.viewport().size();
+            self.scale = self.clamp_scale(mode.scale_for(self.oriented_size(), viewport));
+        }
+        // Keep the image in bounds at its new size.
+        self.translate(0, 0);
+    }
+
+    /// Clamp a scale to the configured bounds.
+    ///
+    /// The scale is also kept within the range where the scaled image is at least one pixel in
+    /// size, and no larger than [`MAX_EXTENT`].
+    fn clamp_scale(&self, scale: f32) -> f32 {
+        let (w, h) = self.image_rect.size();
+        let smallest = w.min(h).max(1) as f32;
+        let largest = w.max(h).max(1) as f32;
+        // Not using `f32::clamp`, since that panics if the bounds are out of order.
+        scale
+            .max(self.config.zoom.min)
+            .min(self.config.zoom.max)
+            .max(smallest.recip())
+            .min(MAX_EXTENT / largest)
+    }
+
+    fn window_resized(&mut self) {
+        self.apply_zoom_mode();
+    }
+
+    fn toggle_smoothing(&mut self) {
+        self.smoothing = !self.smoothing;
+        self.show_message(format!("Smoothing {}", on_off(self.smoothing)));
+    }
+
+    fn toggle_pixel_grid(&mut self) {
+        self.pixel_grid = !self.pixel_grid;
+        self.show_message(format!("Pixel grid {}", on_off(self.pixel_grid)));
+    }
+
+    fn toggle_osd(&mut self) {
+        self.osd = !self.osd;
+        self.show_message(format!("OSD {}", on_off(self.osd)));
+    }
+
+    fn toggle_help(&mut self) {
+        self.help = !self.help;
         self.dirty = true;
     }
 
+    /// Show a message on the OSD for the configured duration.
+    ///
+    /// Messages are shown even while the rest of the OSD is hidden.
+    fn show_message(&mut self, message: String) {
+        let duration = Duration::try_from_secs_f32(self.config.osd.message_duration)
+            .unwrap_or(Duration::ZERO)
+            .min(MAX_MESSAGE_DURATION);
+        self.message = Some((message, Instant::now() + duration));
+        self.dirty = true;
+    }
+
+    fn toggle_playback(&mut self) {
+        if let Slide::Animation { animation, .. } = &mut self.slide {
+            animation.toggle_playback();
+        }
+    }
+
+    /// Pause the animation, and step it forward or backward by a number of frames.
+    fn step_animation(&mut self, frames: i32) {
+        if let Slide::Animation { animation, .. } = &mut self.slide {
+            animation.step(frames);
+            self.dirty = true;
+        }
+    }
+
     fn toggle_fullscreen(&mut self) {
         let w = self.canvas.window_mut();
         let t = match w.fullscreen_state() {
@@ -242,47 +1492,194 @@ impl<'a> AppView<'a> {
         let _ = w.set_fullscreen(t);
     }
 
+    /// Move through the image list using `step`, then display the new current image.
+    ///
+    /// `backward` should be set if `step` moves towards the start of the list.
+    fn navigate(&mut self, step: fn(&mut ImageList) -> bool, backward: bool) {
+        if step(&mut self.images) {
+            self.backward = backward;
+            self.open_current();
+        }
+    }
+
+    /// Display the image at an index in the list.
+    fn go_to(&mut self, index: usize) {
+        let backward = index < self.images.index();
+        if index >= self.images.len() {
+            self.show_message(format!("There are only {} images", self.images.len()));
+        } else if self.images.go_to(index) {
+            self.backward = backward;
+            self.open_current();
+        }
+    }
+
+    /// Replace the image list with a file or directory, as if it was given on the command line.
+    ///
+    /// The current list is kept if there are no images to open.
+    fn open_path(&mut self, path: &Path) {
+        if !path.exists() {
+            self.show_message(format!("\"{}\" doesn't exist", path.display()));
+            return;
+        }
+        let images = ImageList::from_args(vec![path.to_path_buf()], self.recursive);
+        if images.is_empty() {
+            self.show_message(format!("No images in \"{}\"", path.display()));
+            return;
+        }
+        self.images = images;
+        self.backward = false;
+        self.open_current();
+    }
+
+    /// Change an option, and show its new value on the OSD.
+    fn set_option(&mut self, name: &str, value: &str) {
+        if let Err(err) = self.config.set(name, value) {
+            self.show_message(err);
+            return;
+        }
+        // These options set the initial state of something which can be toggled.
+        match name {
+            "pixel_grid.enabled" => self.pixel_grid = self.config.pixel_grid.enabled,
+            "osd.enabled" => self.osd = self.config.osd.enabled,
+            "zoom.mode" => self.zoom_mode = Some(self.config.zoom.mode),
+            _ => (),
+        }
+        // The viewport may have changed size, and the scale may be out of bounds.
+        self.scale = self.clamp_scale(self.scale);
+        self.apply_zoom_mode();
+        self.show_message(format!("{name} = {value}"));
+    }
+
     fn quit(&mut self) {
         self.is_running = false;
     }
 }
 
+/// Format a size in bytes for display, using binary units.
+fn format_file_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = UNITS[0];
+    for next in &UNITS[1..] {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = next;
+    }
+    format!("{size:.1} {unit}")
+}
+
+/// Describe a setting's state in a message.
+fn on_off(enabled: bool) -> &'static str {
+    if enabled {
+        "on"
+    } else {
+        "off"
+    }
+}
+
 // --- Command --- /////////////////////////////////////////
 
 /// Commands extend the basic [`Action`] enum with more advanced options.
 ///
 /// This is made necessary by mouse interaction, since actions only define shifts--translations by
-/// fixed amounts--whereas the mouse should move the image fluidly.
-#[derive(Clone, Copy, Debug)]
+/// fixed amounts--whereas the mouse should move the image fluidly. The command line also uses
+/// commands, for operations which take arguments.
+#[derive(Clone, Debug)]
 pub enum Command {
     /// Translate the image by an arbitrary distance.
     Translate(i32, i32),
+    /// Zoom by a number of mouse wheel steps, keeping the point `(x, y)` in place.
+    ///
+    /// The point is given in window coordinates. Positive steps zoom in, negative steps zoom out.
+    ZoomAt { steps: f32, x: i32, y: i32 },
+    /// Zoom by a factor, keeping the center of the viewport in place.
+    Zoom(f32),
+    /// Set the scale, keeping the center of the viewport in place.
+    ZoomTo(f32),
+    /// Rotate by a number of quarter turns clockwise.
+    Rotate(i32),
+    /// Display the image at an index in the list.
+    GoTo(usize),
+    /// Replace the image list with a file or directory, as if it was given on the command line.
+    Open(PathBuf),
+    /// Change an option, given its name in the config file and its value.
+    Set(String, String),
     /// Wraps a basic action so it can be passed through.
     Wrap(Action),
 }
 
 impl Command {
-    /// Try to construct a new `Command` from a given event, using the given keybind set.
+    /// Whether doing the command more than once does more than doing it once, so it makes sense
+    /// to repeat it by a count.
+    ///
+    /// Toggles, absolute moves like `ZoomTo`, and anything which opens or changes something else
+    /// aren't repeatable.
+    fn is_repeatable(&self) -> bool {
+        match self {
+            Self::Translate(..) | Self::ZoomAt { .. } | Self::Zoom(_) | Self::Rotate(_) => true,
+            Self::ZoomTo(_) | Self::GoTo(_) | Self::Open(_) | Self::Set(..) => false,
+            Self::Wrap(action) => matches!(
+                action,
+                Action::ShiftUp
+                    | Action::ShiftDown
+                    | Action::ShiftLeft
+                    | Action::ShiftRight
+                    | Action::ZoomIn
+                    | Action::ZoomOut
+                    | Action::RotateClockwise
+                    | Action::RotateCounterClockwise
+                    | Action::FlipHorizontal
+                    | Action::FlipVertical
+                    | Action::NextFrame
+                    | Action::PrevFrame
+                    | Action::NextImage
+                    | Action::PrevImage
+            ),
+        }
+    }
+}
+
+impl From<BoundAction> for Command {
+    fn from(action: BoundAction) -> Self {
+        match action {
+            BoundAction::Action(action) => Self::Wrap(action),
+            BoundAction::Shift { dx, dy } => Self::Translate(dx, dy),
+            BoundAction::Zoom(factor) => Self::Zoom(factor),
+            BoundAction::ZoomTo(scale) => Self::ZoomTo(scale),
+            BoundAction::Rotate(quarter_turns) => Self::Rotate(quarter_turns),
+        }
+    }
+}
+
+impl Command {
+    /// Try to construct a new `Command` from a key event, using the given keybind set.
+    ///
+    /// Mouse events are handled by the view, since drags need to keep track of state.
     pub fn try_from_event_using_keymap(event: &Event, binds: &KeyMap) -> Option<Self> {
         match event {
-            Event::MouseMotion {
-                mousestate,
-                xrel,
-                yrel,
+            Event::KeyDown {
+                keycode,
+                scancode,
+                keymod,
                 ..
-            } => mousestate
-                .left()
-                .then_some(Command::Translate(*xrel, *yrel)),
-
-            Event::KeyDown { keycode, .. } => {
-                if let Some(key) = *keycode {
-                    binds.get(&key.into()).map(|action| {
-                        log::info!("mapped {key} to {action:?}");
-                        Command::Wrap(*action)
+            } => {
+                // Binds to the physical key take precedence over binds to the symbol it produces.
+                let modifiers = Modifiers::from(*keymod);
+                [scancode.map(Key::Scan), keycode.map(Key::Code)]
+                    .into_iter()
+                    .flatten()
+                    .map(|key| KeyBind::new(key, modifiers))
+                    .find_map(|bind| {
+                        binds.get(&bind).map(|action| {
+                            log::info!("mapped {bind} to {action:?}");
+                            Command::from(*action)
+                        })
                     })
-                } else {
-                    None
-                }
             }
 
             _ => None,
@@ -0,0 +1,94 @@
+//! Memory-bounded caching of loaded images.
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Identifies a version of a file.
+///
+/// Keys include the file's modification time, so if a file changes on disk, lookups for it miss
+/// the stale entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CacheKey {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl CacheKey {
+    /// Construct a key for the current version of the file at `path`.
+    pub fn new(path: &Path) -> Self {
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        Self {
+            path: path.to_path_buf(),
+            modified,
+        }
+    }
+
+    /// Path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// A cache entry.
+struct Entry<V> {
+    key: CacheKey,
+    value: V,
+    /// Size of the value in bytes.
+    size: usize,
+}
+
+/// A least-recently-used cache, limited by the total size of its values in bytes.
+///
+/// Values are moved out of the cache when they are used, and can be moved back in afterwards.
+/// This suits values which can't be shared, like textures with playback state.
+pub struct Cache<V> {
+    /// Entries, in order from least to most recently inserted.
+    entries: Vec<Entry<V>>,
+    /// Maximum total size of the values, in bytes.
+    budget: usize,
+    /// Current total size of the values, in bytes.
+    used: usize,
+}
+
+impl<V> Cache<V> {
+    /// Construct an empty cache which holds up to `budget` bytes.
+    pub fn new(budget: usize) -> Self {
+        Self {
+            entries: vec![],
+            budget,
+            used: 0,
+        }
+    }
+
+    /// Check if the cache holds a value for `key`.
+    pub fn contains(&self, key: &CacheKey) -> bool {
+        self.entries.iter().any(|e| &e.key == key)
+    }
+
+    /// Move the value for `key` out of the cache.
+    pub fn take(&mut self, key: &CacheKey) -> Option<V> {
+        let index = self.entries.iter().position(|e| &e.key == key)?;
+        let entry = self.entries.remove(index);
+        self.used -= entry.size;
+        Some(entry.value)
+    }
+
+    /// Insert a value of `size` bytes, evicting the least recently used values to make room.
+    ///
+    /// Any other versions of the same file are removed. Values larger than the whole budget are
+    /// not cached at all.
+    pub fn insert(&mut self, key: CacheKey, value: V, size: usize) {
+        self.entries.retain(|e| e.key.path != key.path);
+        self.used = self.entries.iter().map(|e| e.size).sum();
+        if size > self.budget {
+            return;
+        }
+        while self.used + size > self.budget {
+            let evicted = self.entries.remove(0);
+            log::debug!(target: "cache", "evicted \"{}\"", evicted.key.path.display());
+            self.used -= evicted.size;
+        }
+        self.used += size;
+        self.entries.push(Entry { key, value, size });
+    }
+}
@@ -0,0 +1,156 @@
+//! Decoding image files.
+
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::error::{DecodingError, ImageFormatHint};
+use image::metadata::Orientation;
+use image::{AnimationDecoder, DynamicImage, Frames, ImageDecoder, ImageFormat, ImageReader};
+use image::{ImageError, ImageResult, Limits, RgbaImage};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::config::Config;
+
+/// Frames with a delay at or below this are shown for [`DEFAULT_DELAY`] instead.
+///
+/// Many animations are authored with a delay of 0 or 10 ms, and rely on viewers slowing them down
+/// to a sensible speed, so this is what browsers do.
+const MIN_DELAY: Duration = Duration::from_millis(10);
+/// Replacement delay for frames which are too fast. See [`MIN_DELAY`].
+const DEFAULT_DELAY: Duration = Duration::from_millis(100);
+
+/// A decoded image file.
+pub struct Decoded {
+    /// Format of the file, as detected from its contents.
+    pub format: Option<ImageFormat>,
+    /// The image data.
+    pub pixels: Pixels,
+}
+
+/// The image data of a decoded file.
+pub enum Pixels {
+    /// A plain, single-frame image.
+    Still(DynamicImage),
+    /// An animation. Each frame is a complete image, along with how long it should be shown.
+    Animated(Vec<(RgbaImage, Duration)>),
+}
+
+impl Decoded {
+    /// Size of the decoded image data in bytes.
+    pub fn byte_size(&self) -> usize {
+        match &self.pixels {
+            Pixels::Still(image) => image.as_bytes().len(),
+            Pixels::Animated(frames) => frames.iter().map(|(frame, _)| frame.as_raw().len()).sum(),
+        }
+    }
+}
+
+/// Decode the image at `path`.
+///
+/// Animated GIF, PNG and WebP files are decoded into all of their frames. Unless turned off in the
+/// config, images are rotated and mirrored to match their orientation metadata (eg. the EXIF
+/// Orientation tag). The format is detected from the file's contents, not its extension.
+///
+/// The image crate's default limits apply to every format. On top of that, animations stop
+/// decoding once their frames take up the whole decoded image cache, keeping the frames so far.
+pub fn decode(path: &Path, config: &Config) -> ImageResult<Decoded> {
+    let reader = ImageReader::open(path)?.with_guessed_format()?;
+    let format = reader.format();
+    let budget = config.cache.decoded_size.saturating_mul(1024 * 1024);
+    let read_orientation = |decoder: &mut dyn ImageDecoder| {
+        if !config.exif_orientation {
+            return Orientation::NoTransforms;
+        }
+        decoder.orientation().unwrap_or_else(|err| {
+            log::warn!(
+                "failed to read orientation of \"{}\": {err}",
+                path.display()
+            );
+            Orientation::NoTransforms
+        })
+    };
+
+    let pixels = match format {
+        Some(ImageFormat::Gif) => {
+            let mut decoder = GifDecoder::new(reader.into_inner())?;
+            decoder.set_limits(Limits::default())?;
+            let orientation = read_orientation(&mut decoder);
+            collect_frames(decoder.into_frames(), orientation, budget)
+        }
+        Some(ImageFormat::Png) => {
+            let mut decoder = PngDecoder::with_limits(reader.into_inner(), Limits::default())?;
+            let orientation = read_orientation(&mut decoder);
+            if decoder.is_apng()? {
+                collect_frames(decoder.apng()?.into_frames(), orientation, budget)
+            } else {
+                still(decoder, orientation)
+            }
+        }
+        Some(ImageFormat::WebP) => {
+            let mut decoder = WebPDecoder::new(reader.into_inner())?;
+            decoder.set_limits(Limits::default())?;
+            let orientation = read_orientation(&mut decoder);
+            if decoder.has_animation() {
+                collect_frames(decoder.into_frames(), orientation, budget)
+            } else {
+                still(decoder, orientation)
+            }
+        }
+        _ => {
+            let mut decoder = reader.into_decoder()?;
+            let orientation = read_orientation(&mut decoder);
+            still(decoder, orientation)
+        }
+    }?;
+    Ok(Decoded { format, pixels })
+}
+
+/// Decode a single-frame image.
+fn still(decoder: impl ImageDecoder, orientation: Orientation) -> ImageResult<Pixels> {
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+    Ok(Pixels::Still(image))
+}
+
+/// Decode every frame of an animation.
+///
+/// If the animation turns out to have only one frame, it is returned as a still image. An
+/// animation with no frames at all is an error. Once the frames take up `budget` bytes, the rest
+/// of the animation is dropped.
+fn collect_frames(frames: Frames, orientation: Orientation, budget: usize) -> ImageResult<Pixels> {
+    let mut collected = vec![];
+    let mut size = 0;
+    for frame in frames {
+        if size >= budget {
+            log::warn!(
+                "animation is too large, only showing its first {} frames",
+                collected.len()
+            );
+            break;
+        }
+        let frame = frame?;
+        let (numer, denom) = frame.delay().numer_denom_ms();
+        let mut delay =
+            Duration::from_secs_f64(f64::from(numer) / f64::from(denom.max(1)) / 1000.0);
+        if delay <= MIN_DELAY {
+            delay = DEFAULT_DELAY;
+        }
+        let mut image = DynamicImage::ImageRgba8(frame.into_buffer());
+        image.apply_orientation(orientation);
+        let image = image.into_rgba8();
+        size += image.as_raw().len();
+        collected.push((image, delay));
+    }
+
+    match collected.len() {
+        0 => Err(ImageError::Decoding(DecodingError::from_format_hint(
+            ImageFormatHint::Unknown,
+        ))),
+        1 => {
+            let (image, _) = collected.remove(0);
+            Ok(Pixels::Still(image.into()))
+        }
+        _ => Ok(Pixels::Animated(collected)),
+    }
+}
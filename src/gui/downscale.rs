@@ -0,0 +1,117 @@
+//! High-quality downscaling of the displayed image.
+//!
+//! GPU texture filtering gets blurry or aliased when an image is shrunk far below 1:1 scale. For
+//! better results, we can resize the image on the CPU with a Lanczos filter, and display the
+//! result at 1:1 instead. This is too slow to do on every frame, so it only happens once the scale
+//! has stopped changing for a moment.
+
+use image::DynamicImage;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::loader::{Downscaled, Loader};
+use super::texture::{TextureFactory, TiledTexture};
+
+/// How long the wanted size has to stay the same before a copy is made.
+const SETTLE_DELAY: Duration = Duration::from_millis(150);
+
+/// Makes downscaled copies of an image, once the wanted size settles.
+///
+/// The resizing itself is done by the [`Loader`]'s worker threads, so a large image doesn't block
+/// the GUI. Only uploading the finished copy happens on the main thread.
+pub struct Downscaler<'a> {
+    /// The most recently made copy, along with its size.
+    copy: Option<((u32, u32), TiledTexture<'a>)>,
+    /// The size wanted next, along with when to make it.
+    pending: Option<((u32, u32), Instant)>,
+    /// The copy being made by the loader, as its job ID and size.
+    in_progress: Option<(u64, (u32, u32))>,
+    /// ID of the next job sent to the loader. Results of older jobs are ignored.
+    next_id: u64,
+}
+
+impl<'a> Downscaler<'a> {
+    /// Construct a new downscaler, with no copy.
+    pub fn new() -> Self {
+        Self {
+            copy: None,
+            pending: None,
+            in_progress: None,
+            next_id: 0,
+        }
+    }
+
+    /// Drop the copy and any pending request, eg. because the image changed.
+    ///
+    /// A copy which the loader is still making is dropped once it's finished.
+    pub fn clear(&mut self) {
+        self.copy = None;
+        self.pending = None;
+        self.in_progress = None;
+    }
+
+    /// Set the size of copy which is wanted, or `None` if no copy is wanted.
+    ///
+    /// Each time the wanted size changes, the delay before the copy is made restarts.
+    pub fn want(&mut self, size: Option<(u32, u32)>, now: Instant) {
+        let Some(size) = size else {
+            self.clear();
+            return;
+        };
+        let made = self.copy.as_ref().is_some_and(|(s, _)| *s == size);
+        let in_progress = self.in_progress.is_some_and(|(_, s)| s == size);
+        if made || in_progress {
+            self.pending = None;
+        } else if self.pending.is_none_or(|(s, _)| s != size) {
+            self.pending = Some((size, now + SETTLE_DELAY));
+        }
+    }
+
+    /// When the pending copy is due to be made, if there is one.
+    pub fn next_wakeup(&self) -> Option<Instant> {
+        self.pending.map(|(_, due)| due)
+    }
+
+    /// Ask the loader to make the pending copy of `source`, if it's due.
+    pub fn update(&mut self, now: Instant, source: &Arc<DynamicImage>, loader: &mut Loader) {
+        let Some((size, due)) = self.pending else {
+            return;
+        };
+        if due > now {
+            return;
+        }
+        self.pending = None;
+        let id = self.next_id;
+        self.next_id += 1;
+        self.in_progress = Some((id, size));
+        loader.downscale(id, source.clone(), size);
+    }
+
+    /// Upload a copy made by the loader, if it's the one which is wanted.
+    ///
+    /// Returns `true` if a new copy is ready.
+    pub fn finish(&mut self, result: Downscaled, texture_factory: &'a TextureFactory) -> bool {
+        let Some((_, size)) = self.in_progress.filter(|(id, _)| *id == result.id) else {
+            return false;
+        };
+        self.in_progress = None;
+        match texture_factory.construct_from_image(&result.image) {
+            Ok(texture) => {
+                self.copy = Some((size, texture));
+                true
+            }
+            Err(err) => {
+                log::error!("failed to create downscaled texture: {err}");
+                false
+            }
+        }
+    }
+
+    /// The copy, if one of the given size is ready.
+    pub fn get(&self, size: (u32, u32)) -> Option<&TiledTexture<'a>> {
+        self.copy
+            .as_ref()
+            .filter(|(s, _)| *s == size)
+            .map(|(_, texture)| texture)
+    }
+}
@@ -0,0 +1,218 @@
+//! Decoding images in the background.
+//!
+//! Decoding a large image can take a long time, so it's done on worker threads to keep the GUI
+//! responsive. The workers also make high-quality downscaled copies, which are slow for the same
+//! reason. Only the CPU work happens on the workers; uploading the results into textures has to be
+//! done on the main thread, since SDL2 renderers aren't thread-safe.
+
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageResult};
+use sdl2::event::EventSender;
+use sdl2::EventSubsystem;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+
+use super::decode::{decode, Decoded};
+use crate::config::Config;
+
+/// Maximum number of worker threads.
+const MAX_WORKERS: usize = 3;
+
+/// Custom SDL2 event which is pushed when a job finishes, to wake up the event loop.
+pub struct LoadFinished;
+
+/// A request to resize an image with a Lanczos filter.
+struct DownscaleJob {
+    /// Identifies the request, so stale results can be told apart.
+    id: u64,
+    /// The full-size image.
+    source: Arc<DynamicImage>,
+    /// Size to resize the image to.
+    size: (u32, u32),
+}
+
+/// A downscaled copy made by a worker.
+pub struct Downscaled {
+    /// ID of the request this copy was made for.
+    pub id: u64,
+    /// The resized image.
+    pub image: DynamicImage,
+}
+
+/// Something for a worker to do.
+enum Job {
+    Decode(PathBuf),
+    Downscale(DownscaleJob),
+}
+
+/// Queue of jobs waiting to be done, shared between the loader and its workers.
+#[derive(Default)]
+struct Queue {
+    /// Paths to decode, in order of priority.
+    jobs: VecDeque<PathBuf>,
+    /// Image to downscale. This is for the displayed image, so it's done before any decodes.
+    downscale: Option<DownscaleJob>,
+    /// Set when the loader is dropped, telling the workers to exit.
+    closed: bool,
+}
+
+/// Decodes and downscales images on a pool of worker threads.
+///
+/// Requests are handled in the order given. Results can be collected with [`Self::poll`]; each
+/// time a result is ready, a [`LoadFinished`] event is pushed to wake up the event loop.
+pub struct Loader {
+    queue: Arc<(Mutex<Queue>, Condvar)>,
+    results: Receiver<(PathBuf, ImageResult<Decoded>)>,
+    downscaled: Receiver<Downscaled>,
+    /// Paths which have been requested, but whose results haven't been collected yet.
+    pending: Vec<PathBuf>,
+}
+
+impl Loader {
+    /// Construct a new loader, and start its worker threads.
+    pub fn new(config: &Config, events: &EventSubsystem) -> Result<Self, String> {
+        events.register_custom_event::<LoadFinished>()?;
+
+        let queue = Arc::new((Mutex::new(Queue::default()), Condvar::new()));
+        let (sender, results) = mpsc::channel();
+        let (downscaled_sender, downscaled) = mpsc::channel();
+        let workers = std::thread::available_parallelism().map_or(1, |n| n.get().min(MAX_WORKERS));
+        for i in 0..workers {
+            let worker = Worker {
+                queue: queue.clone(),
+                results: sender.clone(),
+                downscaled: downscaled_sender.clone(),
+                events: events.event_sender(),
+                config: config.clone(),
+            };
+            std::thread::Builder::new()
+                .name(format!("loader-{i}"))
+                .spawn(move || worker.run())
+                .map_err(|err| err.to_string())?;
+        }
+
+        Ok(Self {
+            queue,
+            results,
+            downscaled,
+            pending: vec![],
+        })
+    }
+
+    /// Replace the queue of requested paths.
+    ///
+    /// The paths are decoded in the order given. Paths which were queued by an earlier request,
+    /// but are not in this one, are dropped from the queue. Paths which are already being decoded
+    /// are not requested again.
+    pub fn request(&mut self, paths: &[&Path]) {
+        let (lock, condvar) = &*self.queue;
+        let mut queue = lock.lock().unwrap();
+
+        // Whatever's left in the queue was never started, so it's no longer pending.
+        self.pending.retain(|p| !queue.jobs.contains(p));
+        queue.jobs.clear();
+        for &path in paths {
+            if !self.is_pending(path) {
+                queue.jobs.push_back(path.to_path_buf());
+                self.pending.push(path.to_path_buf());
+            }
+        }
+        condvar.notify_all();
+    }
+
+    /// Ask for a copy of `source` resized to `size`, identified by `id`.
+    ///
+    /// This replaces any earlier request which hasn't been started yet.
+    pub fn downscale(&mut self, id: u64, source: Arc<DynamicImage>, size: (u32, u32)) {
+        let (lock, condvar) = &*self.queue;
+        let mut queue = lock.lock().unwrap();
+        queue.downscale = Some(DownscaleJob { id, source, size });
+        condvar.notify_one();
+    }
+
+    /// Check if a path has been requested, but its result hasn't been collected yet.
+    pub fn is_pending(&self, path: &Path) -> bool {
+        self.pending.iter().any(|p| p == path)
+    }
+
+    /// Collect the results of any finished decodes.
+    pub fn poll(&mut self) -> Vec<(PathBuf, ImageResult<Decoded>)> {
+        let results = self.results.try_iter().collect::<Vec<_>>();
+        self.pending
+            .retain(|p| !results.iter().any(|(path, _)| path == p));
+        results
+    }
+
+    /// Collect any finished downscaled copies.
+    pub fn poll_downscaled(&mut self) -> Vec<Downscaled> {
+        self.downscaled.try_iter().collect()
+    }
+}
+
+impl Drop for Loader {
+    /// Tell the workers to exit.
+    ///
+    /// The workers aren't joined, since they may be in the middle of a long decode. They exit as
+    /// soon as they finish.
+    fn drop(&mut self) {
+        let (lock, condvar) = &*self.queue;
+        if let Ok(mut queue) = lock.lock() {
+            queue.closed = true;
+        }
+        condvar.notify_all();
+    }
+}
+
+/// State owned by each worker thread.
+struct Worker {
+    queue: Arc<(Mutex<Queue>, Condvar)>,
+    results: Sender<(PathBuf, ImageResult<Decoded>)>,
+    downscaled: Sender<Downscaled>,
+    events: EventSender,
+    config: Config,
+}
+
+impl Worker {
+    /// Do queued jobs until the loader is dropped.
+    fn run(self) {
+        while let Some(job) = self.next_job() {
+            let sent = match job {
+                Job::Decode(path) => {
+                    let result = decode(&path, &self.config);
+                    self.results.send((path, result)).is_ok()
+                }
+                Job::Downscale(DownscaleJob { id, source, size }) => {
+                    let (w, h) = size;
+                    let image = source.resize_exact(w.max(1), h.max(1), FilterType::Lanczos3);
+                    self.downscaled.send(Downscaled { id, image }).is_ok()
+                }
+            };
+            if !sent {
+                break;
+            }
+            if let Err(err) = self.events.push_custom_event(LoadFinished) {
+                log::error!("failed to push event: {err}");
+            }
+        }
+    }
+
+    /// Wait for the next job, or return `None` if the loader was dropped.
+    fn next_job(&self) -> Option<Job> {
+        let (lock, condvar) = &*self.queue;
+        let mut queue = lock.lock().ok()?;
+        loop {
+            if queue.closed {
+                return None;
+            }
+            if let Some(job) = queue.downscale.take() {
+                return Some(Job::Downscale(job));
+            }
+            if let Some(path) = queue.jobs.pop_front() {
+                return Some(Job::Decode(path));
+            }
+            queue = condvar.wait(queue).ok()?;
+        }
+    }
+}
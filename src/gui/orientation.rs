@@ -0,0 +1,65 @@
+//! Rotation and mirroring of the displayed image.
+
+/// The rotation and mirroring applied to the displayed image.
+///
+/// Flips are stored in texture space, since that is how [`sdl2::render::Canvas::copy_ex`] applies
+/// them: the texture is flipped first, and then rotated. The flip methods take this into account,
+/// so they always mirror the image as it appears on screen.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Orientation {
+    /// Number of clockwise quarter turns, from 0 to 3.
+    quarter_turns: u8,
+    /// Whether the texture is mirrored horizontally.
+    flip_horizontal: bool,
+    /// Whether the texture is mirrored vertically.
+    flip_vertical: bool,
+}
+
+impl Orientation {
+    /// Rotate by a number of quarter turns. Positive values turn clockwise.
+    pub fn rotate(&mut self, quarter_turns: i32) {
+        self.quarter_turns = (i32::from(self.quarter_turns) + quarter_turns).rem_euclid(4) as u8;
+    }
+
+    /// Mirror the image horizontally, as seen on screen.
+    pub fn flip_horizontal(&mut self) {
+        if self.is_sideways() {
+            self.flip_vertical = !self.flip_vertical;
+        } else {
+            self.flip_horizontal = !self.flip_horizontal;
+        }
+    }
+
+    /// Mirror the image vertically, as seen on screen.
+    pub fn flip_vertical(&mut self) {
+        if self.is_sideways() {
+            self.flip_horizontal = !self.flip_horizontal;
+        } else {
+            self.flip_vertical = !self.flip_vertical;
+        }
+    }
+
+    /// Whether the image is turned on its side, swapping its width and height.
+    pub fn is_sideways(&self) -> bool {
+        self.quarter_turns % 2 == 1
+    }
+
+    /// Apply the orientation to an image size, giving the size of its bounding box.
+    pub fn apply_to_size(&self, (w, h): (u32, u32)) -> (u32, u32) {
+        if self.is_sideways() {
+            (h, w)
+        } else {
+            (w, h)
+        }
+    }
+
+    /// Clockwise rotation angle in degrees, as taken by `copy_ex`.
+    pub fn angle(&self) -> f64 {
+        f64::from(self.quarter_turns) * 90.0
+    }
+
+    /// Texture flips, as taken by `copy_ex`.
+    pub fn flips(&self) -> (bool, bool) {
+        (self.flip_horizontal, self.flip_vertical)
+    }
+}
@@ -0,0 +1,396 @@
+//! The command line, opened with the CommandPrompt action.
+//!
+//! Commands are typed like in vim, after a `:`. The built-in commands are:
+//! - `zoom 150` sets the scale as a percentage. `scale 150` does the same.
+//! - `goto 42` displays the image at that position in the list, counting from 1.
+//! - `rotate 90` rotates the image clockwise by a multiple of 90 degrees, like the `Rotate`
+//!   action.
+//! - `set grid.size 8` changes an option, as named in the config file.
+//! - `open path` replaces the image list with a file or directory.
+//!
+//! Anything else is parsed as an action, the same way as in the binds table, such as `NextImage`
+//! or `Shift 0 -64`. Action names start with a capital letter, and the built-in commands don't,
+//! so `Zoom 2` doubles the scale while `zoom 200` sets it.
+
+use std::path::PathBuf;
+
+use super::appview::Command;
+use crate::config::{parse_scale, quarter_turns, BoundAction, Config};
+
+/// Names of the built-in commands.
+const COMMANDS: [&str; 6] = ["goto", "open", "rotate", "scale", "set", "zoom"];
+
+/// The state of the command line while it's open.
+#[derive(Clone, Debug, Default)]
+pub struct Prompt {
+    /// Text typed so far, not including the `:`.
+    input: String,
+    /// Tab completion in progress, if the user is cycling through candidates.
+    completion: Option<Completion>,
+}
+
+/// Candidates for the word being completed, which Tab cycles through.
+#[derive(Clone, Debug)]
+struct Completion {
+    /// Byte offset in the input where the word starts.
+    start: usize,
+    /// Every word the input could be completed to.
+    candidates: Vec<String>,
+    /// Index of the candidate in the input.
+    index: usize,
+}
+
+impl Prompt {
+    /// Text typed so far, not including the `:`.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// Add typed text to the end of the input.
+    pub fn insert(&mut self, text: &str) {
+        self.completion = None;
+        self.input.push_str(text);
+    }
+
+    /// Delete the last character of the input.
+    ///
+    /// Returns `false` if the input was already empty, in which case the prompt should close.
+    pub fn backspace(&mut self) -> bool {
+        self.completion = None;
+        self.input.pop().is_some()
+    }
+
+    /// Complete the word at the end of the input.
+    ///
+    /// Words are completed as far as all of the candidates agree. Once they don't, pressing Tab
+    /// again cycles through the candidates.
+    pub fn complete(&mut self) {
+        if let Some(completion) = &mut self.completion {
+            completion.index = (completion.index + 1) % completion.candidates.len();
+            let candidate = &completion.candidates[completion.index];
+            self.input.replace_range(completion.start.., candidate);
+            return;
+        }
+
+        let (start, candidates) = self.candidates();
+        let word_len = self.input.len() - start;
+        match candidates.as_slice() {
+            [] => (),
+            [only] => {
+                self.input.replace_range(start.., only);
+                // Move on to the next word, unless a directory could be completed further.
+                if !only.ends_with('/') && !self.is_completing_path() {
+                    self.input.push(' ');
+                }
+            }
+            [first, ..] => {
+                let common = common_prefix(&candidates);
+                if common.len() > word_len {
+                    self.input.replace_range(start.., common);
+                } else {
+                    self.input.replace_range(start.., first);
+                    self.completion = Some(Completion {
+                        start,
+                        candidates,
+                        index: 0,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Whether the word at the end of the input is a path.
+    fn is_completing_path(&self) -> bool {
+        let input = self.input.trim_start();
+        input
+            .split_once(char::is_whitespace)
+            .is_some_and(|(command, _)| command == "open")
+    }
+
+    /// The words which the word at the end of the input could be completed to, and the byte
+    /// offset where that word starts.
+    ///
+    /// Names which match the word with the same case are preferred, so `ro` completes to `rotate`
+    /// and `Ro` to the `Rotate` actions. If there are none, names are matched regardless of case,
+    /// so `next` completes to `NextImage`.
+    fn candidates(&self) -> (usize, Vec<String>) {
+        let offset = self.input.len() - self.input.trim_start().len();
+        let input = &self.input[offset..];
+        let matching = |names: Vec<String>, word: &str| {
+            let (mut exact, ignoring_case) = names
+                .into_iter()
+                .filter(|name| name.to_lowercase().starts_with(&word.to_lowercase()))
+                .partition::<Vec<_>, _>(|name| name.starts_with(word));
+            if exact.is_empty() {
+                exact = ignoring_case;
+            }
+            exact.sort();
+            exact
+        };
+
+        let Some((command, args)) = input.split_once(char::is_whitespace) else {
+            let names = COMMANDS
+                .map(String::from)
+                .into_iter()
+                .chain(BoundAction::names())
+                .collect();
+            return (offset, matching(names, input));
+        };
+        let word = args.trim_start();
+        let start = self.input.len() - word.len();
+        match command {
+            "open" => (start, complete_path(word)),
+            "set" if !word.contains(char::is_whitespace) => {
+                let names = Config::RUNTIME_OPTIONS
+                    .iter()
+                    .copied()
+                    .map(String::from)
+                    .collect();
+                (start, matching(names, word))
+            }
+            _ => (start, vec![]),
+        }
+    }
+}
+
+/// Parse a line typed into the command line.
+pub fn parse(line: &str) -> Result<Command, String> {
+    let line = line.trim();
+    let (name, args) = line
+        .split_once(char::is_whitespace)
+        .map_or((line, ""), |(name, args)| (name, args.trim_start()));
+    let usage = |usage: &str| format!("usage: {name} {usage}");
+
+    match name {
+        "zoom" | "scale" if !args.is_empty() => {
+            parse_scale(&format!("{}%", args.trim_end_matches('%'))).map(Command::ZoomTo)
+        }
+        "zoom" | "scale" => Err(usage("<percent>")),
+        "goto" => match args.parse::<usize>() {
+            Ok(number) if number > 0 => Ok(Command::GoTo(number - 1)),
+            _ => Err(usage("<number>")),
+        },
+        "rotate" => args
+            .parse()
+            .map_err(|_| usage("<degrees>"))
+            .and_then(quarter_turns)
+            .map(Command::Rotate),
+        "set" => match args.split_once(char::is_whitespace) {
+            Some((option, value)) => Ok(Command::Set(
+                option.to_string(),
+                value.trim_start().to_string(),
+            )),
+            None => Err(usage("<option> <value>")),
+        },
+        "open" if !args.is_empty() => Ok(Command::Open(expand_home(args))),
+        "open" => Err(usage("<path>")),
+        _ => line
+            .parse::<BoundAction>()
+            .map(Command::from)
+            .map_err(|err| {
+                // Only explain what's wrong with the parameters if the name is an action at all.
+                if BoundAction::names().any(|action| action == name) {
+                    err
+                } else {
+                    format!("unknown command \"{name}\"")
+                }
+            }),
+    }
+}
+
+/// Replace a leading `~` in a path with the user's home directory.
+fn expand_home(path: &str) -> PathBuf {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    match (path.strip_prefix('~'), home) {
+        (Some(""), Some(home)) => home,
+        (Some(rest), Some(home)) if rest.starts_with('/') => home.join(&rest[1..]),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// List the files and directories which a partly typed path could be completed to.
+///
+/// Directories end with a `/`, so they can be completed further. Hidden files are only listed
+/// if the name being completed starts with a `.`.
+fn complete_path(word: &str) -> Vec<String> {
+    let (dir, prefix) = word.split_at(word.rfind('/').map_or(0, |i| i + 1));
+    let read_path = if dir.is_empty() {
+        PathBuf::from(".")
+    } else {
+        expand_home(dir)
+    };
+    let Ok(entries) = std::fs::read_dir(&read_path) else {
+        return vec![];
+    };
+
+    let mut candidates = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            // Follow symbolic links, so links to directories can be completed into.
+            let slash = if read_path.join(&name).is_dir() {
+                "/"
+            } else {
+                ""
+            };
+            Some(format!("{dir}{name}{slash}"))
+        })
+        .collect::<Vec<_>>();
+    candidates.sort();
+    candidates
+}
+
+/// The longest prefix shared by all of the strings.
+fn common_prefix(strings: &[String]) -> &str {
+    let Some((first, rest)) = strings.split_first() else {
+        return "";
+    };
+    let len = rest.iter().fold(first.len(), |len, s| {
+        first[..len]
+            .char_indices()
+            .zip(s.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(len.min(s.len()), |((i, _), _)| i)
+    });
+    first.get(..len).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Action;
+    use std::path::Path;
+
+    /// Open a prompt with some text typed into it.
+    fn typed(input: &str) -> Prompt {
+        let mut prompt = Prompt::default();
+        prompt.insert(input);
+        prompt
+    }
+
+    #[test]
+    fn parses_builtin_commands() {
+        assert!(matches!(parse("zoom 150"), Ok(Command::ZoomTo(scale)) if scale == 1.5));
+        assert!(matches!(parse("scale 150"), Ok(Command::ZoomTo(scale)) if scale == 1.5));
+        assert!(matches!(parse("scale 50%"), Ok(Command::ZoomTo(scale)) if scale == 0.5));
+        assert!(matches!(parse("  goto 3 "), Ok(Command::GoTo(2))));
+        assert!(matches!(parse("rotate -90"), Ok(Command::Rotate(-1))));
+        assert!(matches!(
+            parse("set status_bar.position Top"),
+            Ok(Command::Set(option, value)) if option == "status_bar.position" && value == "Top"
+        ));
+        assert!(matches!(
+            parse("open /tmp/some dir"),
+            Ok(Command::Open(path)) if path == Path::new("/tmp/some dir")
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert_eq!(parse("zoom").unwrap_err(), "usage: zoom <percent>");
+        assert_eq!(parse("scale").unwrap_err(), "usage: scale <percent>");
+        assert!(parse("scale 0").is_err());
+        assert_eq!(parse("goto 0").unwrap_err(), "usage: goto <number>");
+        assert!(parse("goto next").is_err());
+        assert!(parse("rotate 45").is_err());
+        assert_eq!(
+            parse("set grid.size").unwrap_err(),
+            "usage: set <option> <value>"
+        );
+        assert_eq!(parse("open").unwrap_err(), "usage: open <path>");
+    }
+
+    #[test]
+    fn parses_actions() {
+        assert!(matches!(
+            parse("NextImage"),
+            Ok(Command::Wrap(Action::NextImage))
+        ));
+        assert!(matches!(parse("Zoom 2"), Ok(Command::Zoom(factor)) if factor == 2.0));
+        assert!(matches!(parse("Rotate 90"), Ok(Command::Rotate(1))));
+        assert_eq!(
+            parse("Frobnicate").unwrap_err(),
+            "unknown command \"Frobnicate\""
+        );
+        // Errors in the parameters of a real action are passed through.
+        assert!(!parse("Zoom big")
+            .unwrap_err()
+            .starts_with("unknown command"));
+    }
+
+    #[test]
+    fn finds_common_prefixes() {
+        let strings = |list: &[&str]| list.iter().copied().map(String::from).collect::<Vec<_>>();
+        assert_eq!(common_prefix(&[]), "");
+        assert_eq!(common_prefix(&strings(&["zoom.max"])), "zoom.max");
+        assert_eq!(common_prefix(&strings(&["zoom.max", "zoom.min"])), "zoom.m");
+        assert_eq!(
+            common_prefix(&strings(&["Zoom", "ZoomIn", "ZoomTo"])),
+            "Zoom"
+        );
+        assert_eq!(common_prefix(&strings(&["rotate", "scale"])), "");
+        assert_eq!(common_prefix(&strings(&["éa", "éb"])), "é");
+    }
+
+    #[test]
+    fn completes_unique_names() {
+        let mut prompt = typed("ro");
+        prompt.complete();
+        assert_eq!(prompt.input(), "rotate ");
+
+        let mut prompt = typed("set grid.s");
+        prompt.complete();
+        assert_eq!(prompt.input(), "set grid.size ");
+    }
+
+    #[test]
+    fn completes_as_far_as_candidates_agree() {
+        // Only the actions match, since they have the same case as the input.
+        let mut prompt = typed("Ro");
+        prompt.complete();
+        assert_eq!(prompt.input(), "Rotate");
+    }
+
+    #[test]
+    fn cycles_through_candidates() {
+        let mut prompt = typed("set zoom.m");
+        let mut seen = vec![];
+        for _ in 0..4 {
+            prompt.complete();
+            seen.push(prompt.input().to_string());
+        }
+        assert_eq!(
+            seen,
+            [
+                "set zoom.max",
+                "set zoom.min",
+                "set zoom.mode",
+                "set zoom.max"
+            ]
+        );
+
+        // Editing the input ends the cycle, so the next Tab completes what's there.
+        prompt.backspace();
+        prompt.complete();
+        assert_eq!(prompt.input(), "set zoom.max ");
+    }
+
+    #[test]
+    fn falls_back_to_matching_any_case() {
+        let mut prompt = typed("next");
+        prompt.complete();
+        assert_eq!(prompt.input(), "NextFrame");
+        prompt.complete();
+        assert_eq!(prompt.input(), "NextImage");
+    }
+
+    #[test]
+    fn backspace_reports_empty_input() {
+        let mut prompt = typed("a");
+        assert!(prompt.backspace());
+        assert!(!prompt.backspace());
+    }
+}
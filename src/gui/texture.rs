@@ -6,11 +6,15 @@
 use std::fmt::Display;
 
 use derive_more::derive::From;
-use image::DynamicImage;
+use image::{DynamicImage, RgbaImage};
 use sdl2::pixels::PixelFormatEnum;
-use sdl2::render::{Texture, TextureCreator, TextureValueError};
+use sdl2::rect::{Point, Rect};
+use sdl2::render::{Canvas, Texture, TextureCreator, TextureValueError};
 use sdl2::surface::Surface;
-use sdl2::video::WindowContext;
+use sdl2::sys::SDL_ScaleMode;
+use sdl2::video::{Window, WindowContext};
+
+use crate::config::ScaleFilter;
 
 // --- TextureFactory --- //////////////////////////////////
 
@@ -20,34 +24,199 @@ use sdl2::video::WindowContext;
 /// as glue between the two.
 pub struct TextureFactory {
     texture_creator: TextureCreator<WindowContext>,
+    /// Largest texture size the renderer supports.
+    max_size: (u32, u32),
 }
 
 impl TextureFactory {
     /// Construct a new TextureFactory using the given [`sdl2::render::TextureCreator`].
-    pub fn new(texture_creator: TextureCreator<WindowContext>) -> Self {
-        Self { texture_creator }
+    ///
+    /// `max_size` is the largest texture size the renderer supports, as reported by
+    /// [`sdl2::render::RendererInfo`]. Renderers which don't report a limit report zero, in which
+    /// case [`FALLBACK_MAX_SIZE`] is used.
+    pub fn new(texture_creator: TextureCreator<WindowContext>, max_size: (u32, u32)) -> Self {
+        let or_fallback = |n: u32| if n == 0 { FALLBACK_MAX_SIZE } else { n };
+        Self {
+            texture_creator,
+            max_size: (or_fallback(max_size.0), or_fallback(max_size.1)),
+        }
     }
 
-    /// Construct an [`sdl2::render::Texture`] from some image data.
+    /// Construct a [`TiledTexture`] from some image data.
+    ///
+    /// The image is split into as many tiles as needed to keep each one within the renderer's
+    /// maximum texture size. Most images fit in a single tile.
     pub fn construct_from_image(
         &self,
         image: &DynamicImage,
-    ) -> Result<Texture, TextureCreationError> {
+    ) -> Result<TiledTexture<'_>, TextureCreationError> {
         let mut data = image.to_rgba8().into_flat_samples();
         let width = data.layout.width;
         let height = data.layout.height;
-        let pitch = data.layout.height_stride as u32;
-        let surf = Surface::from_data(
-            data.as_mut_slice(),
-            width,
-            height,
-            pitch,
-            PixelFormatEnum::RGBA32,
-        )?;
+        let pitch = data.layout.height_stride;
+        let (tile_w, tile_h) = self.max_size;
+
+        let mut tiles = vec![];
+        for y in (0..height).step_by(tile_h as usize) {
+            for x in (0..width).step_by(tile_w as usize) {
+                let rect = Rect::new(
+                    x as i32,
+                    y as i32,
+                    tile_w.min(width - x),
+                    tile_h.min(height - y),
+                );
+                // The surface reads the tile's rows straight out of the full image's buffer.
+                let offset = y as usize * pitch + x as usize * 4;
+                let surf = Surface::from_data(
+                    &mut data.as_mut_slice()[offset..],
+                    rect.width(),
+                    rect.height(),
+                    pitch as u32,
+                    PixelFormatEnum::RGBA32,
+                )?;
+                tiles.push((rect, surf.as_texture(&self.texture_creator)?));
+            }
+        }
+        Ok(TiledTexture {
+            tiles,
+            size: (width, height),
+        })
+    }
+
+    /// Construct a single texture from some image data.
+    ///
+    /// Unlike [`Self::construct_from_image`], the image isn't split into tiles. This is meant for
+    /// small images generated by Fir itself, such as the font used for overlays.
+    pub fn construct_texture(
+        &self,
+        image: &RgbaImage,
+    ) -> Result<Texture<'_>, TextureCreationError> {
+        let (width, height) = image.dimensions();
+        let mut data = image.as_raw().clone();
+        let surf =
+            Surface::from_data(&mut data, width, height, width * 4, PixelFormatEnum::RGBA32)?;
         Ok(surf.as_texture(&self.texture_creator)?)
     }
 }
 
+/// Maximum texture size used if the renderer doesn't report one.
+const FALLBACK_MAX_SIZE: u32 = 4096;
+
+// --- TiledTexture --- ////////////////////////////////////
+
+/// An image stored as a grid of textures.
+///
+/// Renderers have a maximum texture size, which very large images like panoramas and scans can
+/// exceed. Splitting the image into tiles lets us display it anyway. Only the tiles which are
+/// actually visible get drawn.
+pub struct TiledTexture<'a> {
+    /// Each tile, along with the area of the image it covers.
+    tiles: Vec<(Rect, Texture<'a>)>,
+    /// Size of the whole image in pixels.
+    size: (u32, u32),
+}
+
+impl TiledTexture<'_> {
+    /// Size of the whole image in pixels.
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Set the filter used when the textures are scaled.
+    pub fn set_scale_filter(&self, filter: ScaleFilter) {
+        let mode = match filter {
+            ScaleFilter::Nearest => SDL_ScaleMode::SDL_ScaleModeNearest,
+            ScaleFilter::Linear => SDL_ScaleMode::SDL_ScaleModeLinear,
+            ScaleFilter::Best => SDL_ScaleMode::SDL_ScaleModeBest,
+        };
+        for (_, texture) in &self.tiles {
+            // SAFETY: The texture pointer is valid for as long as the texture is alive. The sdl2
+            // crate doesn't wrap this function, but it only changes a setting on the texture.
+            unsafe {
+                sdl2::sys::SDL_SetTextureScaleMode(texture.raw(), mode);
+            }
+        }
+    }
+
+    /// Approximate size of the textures in bytes.
+    pub fn byte_size(&self) -> usize {
+        self.tiles
+            .iter()
+            .map(|(rect, _)| rect.width() as usize * rect.height() as usize * 4)
+            .sum()
+    }
+
+    /// Draw the image into `dst`, then rotate it by `angle` degrees clockwise around the center
+    /// of `dst`.
+    ///
+    /// This works the same as [`Canvas::copy_ex`] does for a single texture. Flips are applied
+    /// before rotation.
+    pub fn draw(
+        &self,
+        canvas: &mut Canvas<Window>,
+        dst: Rect,
+        angle: f64,
+        flip_horizontal: bool,
+        flip_vertical: bool,
+    ) -> Result<(), String> {
+        let (ow, oh) = canvas.output_size()?;
+        let viewport = Rect::new(0, 0, ow, oh);
+        let sx = f64::from(dst.width()) / f64::from(self.size.0);
+        let sy = f64::from(dst.height()) / f64::from(self.size.1);
+
+        for (src, texture) in &self.tiles {
+            // Scale both edges rather than the size, so neighboring tiles meet without gaps.
+            let scale_span = |start: i32, len: u32, scale: f64, flip: bool, total: u32| {
+                let start = if flip {
+                    total as i32 - start - len as i32
+                } else {
+                    start
+                };
+                let a = (f64::from(start) * scale).round() as i32;
+                let b = (f64::from(start + len as i32) * scale).round() as i32;
+                (a, (b - a).max(1) as u32)
+            };
+            let (x, w) = scale_span(src.x(), src.width(), sx, flip_horizontal, self.size.0);
+            let (y, h) = scale_span(src.y(), src.height(), sy, flip_vertical, self.size.1);
+            let tile_dst = Rect::new(dst.x() + x, dst.y() + y, w, h);
+
+            // Rotate around the center of the whole image, not the center of the tile.
+            let center = dst.center() - tile_dst.top_left();
+            if rotated_bounds(tile_dst, dst.center(), angle).has_intersection(viewport) {
+                canvas.copy_ex(
+                    texture,
+                    None,
+                    tile_dst,
+                    angle,
+                    center,
+                    flip_horizontal,
+                    flip_vertical,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Bounding box of `rect` after rotating it by `angle` degrees clockwise around `center`.
+fn rotated_bounds(rect: Rect, center: Point, angle: f64) -> Rect {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let corners = [
+        rect.top_left(),
+        rect.top_right(),
+        rect.bottom_left(),
+        rect.bottom_right(),
+    ]
+    .map(|p| {
+        let (dx, dy) = (f64::from(p.x() - center.x()), f64::from(p.y() - center.y()));
+        Point::new(
+            center.x() + (dx * cos - dy * sin).round() as i32,
+            center.y() + (dx * sin + dy * cos).round() as i32,
+        )
+    });
+    Rect::from_enclose_points(&corners, None).unwrap_or(rect)
+}
+
 // --- TextureCreationError  --- ///////////////////////////
 
 /// Possible failure modes for [`TextureFactory::construct_from_image`].
@@ -0,0 +1,203 @@
+//! The list of images being viewed.
+
+use image::ImageFormat;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// An ordered list of image paths, along with a cursor pointing at the current image.
+///
+/// Navigation methods never move the cursor out of bounds; trying to step past either end of the
+/// list leaves the cursor where it is.
+#[derive(Clone, Debug)]
+pub struct ImageList {
+    paths: Vec<PathBuf>,
+    index: usize,
+}
+
+impl ImageList {
+    /// Construct a new image list. The cursor starts on the first image.
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        Self { paths, index: 0 }
+    }
+
+    /// Build an image list from the paths given on the command line.
+    ///
+    /// Directories are expanded into the images they contain, sorted by name. If `recursive` is
+    /// set, subdirectories are searched as well. Plain files are always kept, in the order given.
+    ///
+    /// As a special case, if only a single file is given, the list holds all of the images in
+    /// that file's directory, and the cursor starts on the given file.
+    pub fn from_args(paths: Vec<PathBuf>, recursive: bool) -> Self {
+        if let [file] = paths.as_slice() {
+            if !file.is_dir() {
+                return Self::with_siblings(file);
+            }
+        }
+
+        let mut images = vec![];
+        for path in paths {
+            if path.is_dir() {
+                let mut found = scan_dir(&path, recursive);
+                found.sort();
+                images.append(&mut found);
+            } else {
+                images.push(path);
+            }
+        }
+        Self::new(images)
+    }
+
+    /// Build a list of all the images in `file`'s directory, with the cursor on `file`.
+    fn with_siblings(file: &Path) -> Self {
+        let dir = file.parent().unwrap_or(Path::new(""));
+        let mut paths = scan_dir(dir, false);
+        if !paths.iter().any(|p| p.file_name() == file.file_name()) {
+            // The file may not be recognized as an image, but the user asked for it explicitly.
+            paths.push(file.to_path_buf());
+        }
+        paths.sort();
+
+        let index = paths
+            .iter()
+            .position(|p| p.file_name() == file.file_name())
+            .unwrap_or(0);
+        Self { paths, index }
+    }
+
+    /// Returns `true` if there are no images in the list.
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Number of images in the list.
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Index of the current image.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Path of the current image, or `None` if the list is empty.
+    pub fn current(&self) -> Option<&Path> {
+        self.paths.get(self.index).map(PathBuf::as_path)
+    }
+
+    /// Path of the image `offset` places away from the current one, if there is one.
+    pub fn peek(&self, offset: isize) -> Option<&Path> {
+        let index = self.index.checked_add_signed(offset)?;
+        self.paths.get(index).map(PathBuf::as_path)
+    }
+
+    /// Move the cursor to the given index.
+    ///
+    /// Returns `true` if the cursor moved.
+    pub fn go_to(&mut self, index: usize) -> bool {
+        let index = index.min(self.paths.len().saturating_sub(1));
+        let moved = index != self.index;
+        self.index = index;
+        moved
+    }
+
+    /// Remove the current image from the list.
+    ///
+    /// The cursor moves to the image which followed the removed one, or to the one before it if
+    /// `backward` is set. At either end of the list, the cursor stays in bounds.
+    pub fn remove_current(&mut self, backward: bool) {
+        if self.index >= self.paths.len() {
+            return;
+        }
+        self.paths.remove(self.index);
+        if backward {
+            self.index = self.index.saturating_sub(1);
+        }
+        self.index = self.index.min(self.paths.len().saturating_sub(1));
+    }
+
+    /// Move the cursor to the next image.
+    pub fn go_next(&mut self) -> bool {
+        self.go_to(self.index.saturating_add(1))
+    }
+
+    /// Move the cursor to the previous image.
+    pub fn go_prev(&mut self) -> bool {
+        self.go_to(self.index.saturating_sub(1))
+    }
+
+    /// Move the cursor to the first image.
+    pub fn go_first(&mut self) -> bool {
+        self.go_to(0)
+    }
+
+    /// Move the cursor to the last image.
+    pub fn go_last(&mut self) -> bool {
+        self.go_to(usize::MAX)
+    }
+}
+
+// --- Functions --- ///////////////////////////////////////
+
+/// Collect the paths of all the images in a directory, unsorted.
+///
+/// Unreadable directories and entries are logged and skipped. Symbolic links to directories are
+/// not followed, so recursion can't get stuck in a cycle.
+fn scan_dir(dir: &Path, recursive: bool) -> Vec<PathBuf> {
+    // An empty parent path means the current directory.
+    let read_path = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let entries = match std::fs::read_dir(read_path) {
+        Ok(entries) => entries,
+        Err(err) => {
+            log::warn!("failed to read \"{}\": {err}", read_path.display());
+            return vec![];
+        }
+    };
+
+    let mut images = vec![];
+    for entry in entries.filter_map(|e| e.map_err(|err| log::warn!("{err}")).ok()) {
+        let path = dir.join(entry.file_name());
+        match entry.file_type() {
+            Ok(t) if t.is_dir() => {
+                if recursive {
+                    images.append(&mut scan_dir(&path, recursive));
+                }
+            }
+            Ok(_) => {
+                if is_image(&path) {
+                    images.push(path);
+                }
+            }
+            Err(err) => log::warn!("\"{}\": {err}", path.display()),
+        }
+    }
+    images
+}
+
+/// Number of bytes at the start of a file which are read to guess its format.
+const HEADER_SIZE: u64 = 16;
+
+/// Formats which can't be identified by their contents, so files with their extension are
+/// assumed to be in them.
+const UNSIGNED_FORMATS: [ImageFormat; 1] = [ImageFormat::Tga];
+
+/// Check whether a file looks like an image we can open.
+///
+/// The format is guessed from the file's contents. The extension is only used as a fallback, for
+/// formats that can't be identified by their content alone, so a text file named `notes.png`
+/// isn't mistaken for an image.
+fn is_image(path: &Path) -> bool {
+    let mut header = vec![];
+    if File::open(path)
+        .and_then(|file| file.take(HEADER_SIZE).read_to_end(&mut header))
+        .is_err()
+    {
+        return false;
+    }
+    image::guess_format(&header).is_ok()
+        || ImageFormat::from_path(path).is_ok_and(|format| UNSIGNED_FORMATS.contains(&format))
+}
@@ -3,12 +3,14 @@
 use cli::args::{parse, Request};
 use cli::print;
 use config::Config;
+use imagelist::ImageList;
 use std::env;
 use std::error::Error;
 
 mod cli;
 mod config;
 mod gui;
+mod imagelist;
 mod meta;
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -27,7 +29,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     };
 
     // Handle decoded arguments.
-    let files = match request {
+    let (images, recursive, skip_broken) = match request {
         Request::Help => {
             print::help();
             return Ok(());
@@ -36,10 +38,23 @@ fn main() -> Result<(), Box<dyn Error>> {
             print::version();
             return Ok(());
         }
-        Request::View { files } => files,
+        Request::View {
+            files,
+            recursive,
+            skip_broken,
+        } => (
+            ImageList::from_args(files, recursive),
+            recursive,
+            skip_broken,
+        ),
     };
+    if images.is_empty() {
+        println!("error: no images found");
+        return Ok(());
+    }
 
-    let config = Config::from_config_toml();
+    let mut config = Config::from_config_toml();
+    config.skip_broken |= skip_broken;
 
-    gui::start(files, config)
+    gui::start(images, config, recursive)
 }